@@ -1,199 +1,6270 @@
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate, TimeDelta};
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::SliceRandom,
+    Rng,
+};
+use rustyline::{error::ReadlineError, DefaultEditor};
 use serde::{Deserialize, Serialize};
 use std::{
-    fs::File,
-    io::{stdin, BufReader, Result, Write},
+    cmp::{Ordering, Reverse},
+    collections::{BTreeMap, BTreeSet, HashSet},
+    env,
+    fmt::{self, Write as _},
+    fs::{self, File, OpenOptions},
+    io::{self, stdin, BufReader, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering as AtomicOrdering},
+        OnceLock,
+    },
+    time::Instant,
 };
 
+// Default location of the saved task list.
+const TASKS_PATH: &str = "tasks.json";
+
+// Default location of deleted tasks, kept until the trash is emptied.
+const TRASH_PATH: &str = "trash.json";
+
+// Default location of completed tasks removed from the list on exit.
+const ARCHIVE_PATH: &str = "archive.json";
+
+// Location of view preferences remembered between sessions.
+const SESSION_PATH: &str = "session.json";
+
+// Location of menu input history kept between interactive sessions.
+const HISTORY_PATH: &str = ".todo_history";
+
+// How alike two task names must be, from 0 to 1, to be offered for merging.
+const SIMILARITY_THRESHOLD: f64 = 0.6;
+
+// Exit codes for scripts: a file couldn't be read or written, the input was invalid, or a
+// task couldn't be found. Success exits with 0.
+const EXIT_FILE_ERROR: i32 = 1;
+const EXIT_INVALID_INPUT: i32 = 2;
+const EXIT_NOT_FOUND: i32 = 3;
+
+// Location of the scratchpad of quick notes, kept apart from the task list.
+const NOTES_PATH: &str = "notes.json";
+
+// Default location of the log of every change made to the task list.
+const AUDIT_LOG_PATH: &str = "audit.log";
+
+// Where `log_action` appends to, set at startup only when the audit log is enabled.
+static AUDIT_LOG: OnceLock<AuditLog> = OnceLock::new();
+
+// Highest task ID handed out or seen in the archive and trash, so IDs of tasks that have
+// left the list are never given to new ones.
+static HIGHEST_ID: AtomicU32 = AtomicU32::new(0);
+
+// Set by `--ephemeral` to stop anything from being written to disk this session.
+static EPHEMERAL: AtomicBool = AtomicBool::new(false);
+
+// Location of the user's settings, written by the first-run setup.
+const CONFIG_PATH: &str = "config.toml";
+
+// Directory holding translations, one `<locale>.toml` file of `key = "text"` pairs each.
+const LOCALE_DIR: &str = "locales";
+
+// Strings loaded for the configured locale, set at startup unless the locale is English.
+static TRANSLATIONS: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+
+// English text for every user-facing string looked up with `t`, used when the configured
+// locale doesn't translate a key.
+const ENGLISH: &[(&str, &str)] = &[
+    ("menu.view_tasks", "View tasks"),
+    ("menu.add_task", "Add a task"),
+    ("menu.complete_task", "Complete task"),
+    ("menu.delete_task", "Delete task"),
+    ("menu.toggle_by_name", "Toggle task by name"),
+    ("menu.show_progress", "Show progress"),
+    ("menu.view_details", "View task details"),
+    ("menu.next_task", "Next task"),
+    ("menu.restore_task", "Restore deleted task"),
+    ("menu.empty_trash", "Empty trash"),
+    ("menu.sort_tasks", "Sort tasks"),
+    (
+        "menu.relative_due_date",
+        "Set due date relative to another task",
+    ),
+    ("menu.copy_task", "Copy task to clipboard"),
+    ("menu.new_from_template", "New task from template"),
+    ("menu.bulk_tag", "Bulk tag tasks"),
+    ("menu.week_agenda", "This week's agenda"),
+    ("menu.edit_task", "Edit task"),
+    ("menu.completion_report", "Completion report"),
+    ("menu.filter_tasks", "Filter tasks"),
+    ("menu.toggle_pin", "Pin/unpin task"),
+    ("menu.add_dependency", "Add dependency"),
+    (
+        "menu.reschedule_overdue",
+        "Reschedule overdue tasks to today",
+    ),
+    ("menu.view_by_tag", "View tasks by tag"),
+    ("menu.completed_today", "Completed today"),
+    ("menu.reverse_order", "Reverse order"),
+    ("menu.toggle_blocked", "Show/hide blocked tasks"),
+    ("menu.export_csv", "Export to CSV"),
+    ("menu.import_csv", "Import from CSV"),
+    ("menu.split_task", "Split task into subtasks"),
+    ("menu.log_time", "Log time on task"),
+    ("menu.estimate_report", "Estimate accuracy report"),
+    ("menu.prune_dependencies", "Clean up missing dependencies"),
+    ("menu.review", "Review tasks one at a time"),
+    ("menu.set_priority", "Set task priority"),
+    ("menu.weekly_report", "Write weekly report to a file"),
+    ("menu.shift_all", "Postpone all pending tasks"),
+    ("menu.open_link", "Open task link"),
+    (
+        "menu.make_repeatable",
+        "Make task recurring or save as template",
+    ),
+    ("menu.bulk_edit_dates", "Edit due dates in a text editor"),
+    ("menu.forecast", "Forecast completion dates"),
+    ("menu.set_status", "Set task status"),
+    ("menu.surprise_me", "Surprise me with a random task"),
+    ("menu.calendar", "Calendar"),
+    ("menu.reset_all", "Reset all tasks to pending"),
+    ("menu.switch_file", "Switch to another tasks file"),
+    ("menu.group_by_tag", "Group tasks by tag"),
+    ("menu.add_note", "Add a quick note"),
+    ("menu.view_notes", "View notes"),
+    ("menu.schedule_undated", "Schedule tasks with no due date"),
+    ("menu.toggle_focus", "Add/remove task from focus list"),
+    ("menu.view_focus", "View focus list"),
+    ("menu.merge_duplicates", "Find and merge duplicate tasks"),
+    ("menu.snapshot", "Save a dated snapshot of the list"),
+    ("menu.bulk_priority", "Set priority of matching tasks"),
+    ("menu.reorder", "Reorder tasks"),
+    ("menu.quit", "Save and quit"),
+    ("prompt.menu", "What would you like to? (eg: '1' or 'add')"),
+    ("error.no_tasks", "No tasks available."),
+    ("error.index_blank", "No task number entered!"),
+    (
+        "error.invalid_index_input",
+        "'{input}' isn't a number, enter a task number from the list!",
+    ),
+    (
+        "error.index_below_one",
+        "Task numbers start at 1, so {num} isn't one!",
+    ),
+    (
+        "error.index_out_of_range",
+        "There is no task {num}, enter a number from 1 to {len}!",
+    ),
+    ("error.invalid_task_index", "Invalid task index!"),
+    ("error.invalid_option", "Invalid option!"),
+    ("status.saving", "Saving work..."),
+    (
+        "prompt.select_complete",
+        "Select a task to mark as complete:",
+    ),
+    ("prompt.select_delete", "Select a task to delete:"),
+    ("prompt.select_view", "Select a task to view:"),
+    ("prompt.select_restore", "Select a task to restore:"),
+    ("prompt.select_reschedule", "Select a task to reschedule:"),
+    (
+        "prompt.select_relative_to",
+        "Select the task its due date is relative to:",
+    ),
+    ("prompt.select_copy", "Select a task to copy:"),
+    ("prompt.select_template", "Select a template:"),
+    (
+        "prompt.select_bulk_tag",
+        "Select tasks to tag (eg: '1,3' or '2-4'):",
+    ),
+    ("prompt.select_edit", "Select a task to edit:"),
+    ("prompt.select_pin", "Select a task to pin or unpin:"),
+    ("prompt.select_blocked", "Select the task that is blocked:"),
+    ("prompt.select_blocker", "Select the task it is waiting on:"),
+    ("prompt.select_tag", "Select a tag:"),
+    ("prompt.select_split", "Select a task to split:"),
+    ("prompt.select_log_time", "Select a task to log time on:"),
+    (
+        "prompt.select_priority",
+        "Select a task to set the priority of:",
+    ),
+    (
+        "prompt.select_open_link",
+        "Select a task to open the link of:",
+    ),
+    ("prompt.select_repeatable", "Select a task to repeat:"),
+    (
+        "prompt.select_status",
+        "Select a task to set the status of:",
+    ),
+    (
+        "prompt.select_focus",
+        "Enter the number of the task to add to or remove from the focus list:",
+    ),
+    ("error.message", "Error: {error}"),
+    (
+        "error.unknown_count_option",
+        "unknown option `{option}`, expected `--count` or `--count=overdue`",
+    ),
+    (
+        "status.ephemeral",
+        "Ephemeral mode: nothing will be written to disk, so changes won't persist.",
+    ),
+    ("status.loaded", "loaded tasks from `{path}`"),
+    ("status.empty_file", "`{path}` is empty, no tasks loaded."),
+    ("error.read_tasks", "Couldn't read `{path}`: {error}"),
+    ("status.no_tasks_loaded", "No tasks loaded."),
+    (
+        "status.deduped_ids",
+        "Warning: {count} task(s) shared an ID with another task and were given new ones.",
+    ),
+    (
+        "status.pruned_on_start",
+        "Removed {count} dependency reference(s) to tasks that no longer exist.",
+    ),
+    (
+        "status.filter_on",
+        "A filter is on, run 'filter' and leave every field blank to clear it",
+    ),
+    (
+        "prompt.minutes_taken",
+        "How many minutes did '{name}' take? (leave blank to skip)",
+    ),
+    ("prompt.confirm_delete", "Delete '{name}'?"),
+    (
+        "error.trash_task",
+        "Couldn't move task to the trash: {error}",
+    ),
+    (
+        "prompt.toggle_name",
+        "Enter the name of the task to toggle:",
+    ),
+    ("error.no_name_match", "No task matches '{name}'!"),
+    ("status.next_up", "Next up:"),
+    ("status.all_done", "Nothing to do, all tasks are complete!"),
+    ("error.read_trash", "Couldn't read the trash: {error}"),
+    ("status.trash_empty", "The trash is empty."),
+    ("error.restore_task", "Couldn't restore task: {error}"),
+    (
+        "prompt.confirm_empty_trash",
+        "Permanently delete everything in the trash?",
+    ),
+    ("status.trash_emptied", "Trash emptied"),
+    ("error.empty_trash", "Couldn't empty the trash: {error}"),
+    (
+        "prompt.sort_by",
+        "Sort by: 1. Name  2. Due date  3. Priority",
+    ),
+    ("error.save_sort", "Couldn't save sort preference: {error}"),
+    ("error.invalid_sort", "Invalid sort option!"),
+    (
+        "prompt.relative_offset",
+        "Enter how many days after that task it is due (negative for before):",
+    ),
+    (
+        "error.offset_days",
+        "Offset must be a whole number of days!",
+    ),
+    ("status.now_due", "'{name}' is now due {date}"),
+    (
+        "error.no_templates",
+        "No templates defined, add some to `{path}`.",
+    ),
+    ("error.invalid_template", "Invalid template!"),
+    (
+        "prompt.due_date",
+        "Enter a due date for '{name}' ({format}):",
+    ),
+    ("prompt.tag_to_add", "Enter a tag to add:"),
+    ("error.tag_blank", "Tag can't be blank!"),
+    ("status.tagged", "Tagged {count} task(s) with '{tag}'"),
+    (
+        "prompt.group_completions",
+        "Group completions by: 1. Day  2. Week",
+    ),
+    (
+        "status.filter_cleared",
+        "Filter cleared, the task list shows every task again",
+    ),
+    ("error.save_view", "Couldn't save view preference: {error}"),
+    ("prompt.keep_filter", "Keep this filter on the task list?"),
+    ("status.pinned", "'{name}' pinned"),
+    ("status.unpinned", "'{name}' unpinned"),
+    (
+        "error.dependency_cycle",
+        "'{name}' can't wait on '{blocker}', as that would make them wait on each other!",
+    ),
+    (
+        "status.now_blocked",
+        "'{name}' is now blocked by '{blocker}'",
+    ),
+    ("status.no_overdue", "No overdue tasks."),
+    (
+        "prompt.confirm_reschedule",
+        "Move {count} overdue task(s) to today?",
+    ),
+    ("status.rescheduled", "Rescheduled {count} task(s) to today"),
+    ("status.no_tags", "No tasks are tagged."),
+    ("error.invalid_tag", "Invalid tag!"),
+    (
+        "status.none_completed_today",
+        "Nothing completed yet today.",
+    ),
+    (
+        "error.save_order",
+        "Couldn't save order preference: {error}",
+    ),
+    (
+        "status.blocked_shown",
+        "Blocked tasks are now shown in the list",
+    ),
+    (
+        "status.blocked_hidden",
+        "Blocked tasks are now hidden until what they wait on is done",
+    ),
+    (
+        "prompt.export_path",
+        "Enter a path to export to (leave blank for tasks.csv):",
+    ),
+    ("status.exported", "Exported {count} task(s) to `{path}`"),
+    ("error.export", "Exporting failed: {error}"),
+    (
+        "prompt.import_path",
+        "Enter a path to import from (leave blank for tasks.csv):",
+    ),
+    ("status.imported", "Imported {count} task(s) from `{path}`"),
+    ("error.import", "Importing failed: {error}"),
+    (
+        "prompt.subtask_names",
+        "Enter a name for each subtask, one per line (leave blank to finish):",
+    ),
+    ("error.no_subtasks", "No subtasks entered!"),
+    ("status.split", "Split '{name}' into {count} subtask(s)"),
+    (
+        "prompt.keep_parent",
+        "Keep '{name}' as a parent of its subtasks?",
+    ),
+    (
+        "prompt.minutes_spent",
+        "Enter minutes spent, or leave blank to start a focus timer:",
+    ),
+    (
+        "status.focusing",
+        "Focusing on '{name}', press Enter to stop...",
+    ),
+    (
+        "status.logged_time",
+        "Logged {time} on '{name}', {total} in total",
+    ),
+    ("status.no_missing_deps", "No missing dependencies found."),
+    (
+        "status.pruned_deps",
+        "Removed {count} reference(s) to tasks that no longer exist",
+    ),
+    (
+        "prompt.priority_or_cycle",
+        "Enter a priority for '{name}' (low/medium/high, leave blank to cycle from {current}):",
+    ),
+    ("error.invalid_priority", "Invalid priority!"),
+    (
+        "prompt.report_path",
+        "Enter a path to write the report to (leave blank for weekly_report.txt):",
+    ),
+    ("status.report_written", "Weekly report written to `{path}`"),
+    ("error.write_report", "Writing the report failed: {error}"),
+    (
+        "prompt.shift_days",
+        "Enter how many days to push back every pending task's due date:",
+    ),
+    (
+        "status.no_dated_pending",
+        "No pending tasks have a due date.",
+    ),
+    (
+        "prompt.confirm_shift",
+        "Move {count} pending task(s) by {days} day(s)?",
+    ),
+    (
+        "error.shift_out_of_range",
+        "Moving by {days} day(s) goes past the last possible date, nothing was moved!",
+    ),
+    ("status.shifted", "Moved {count} task(s) by {days} day(s)"),
+    ("status.opened_link", "Opened {link}"),
+    ("error.open_link", "Couldn't open {link}: {error}"),
+    ("error.no_link", "'{name}' has no link!"),
+    (
+        "prompt.repeat_how",
+        "1. Make it recurring  2. Save it as a template",
+    ),
+    (
+        "prompt.recurrence",
+        "How often should it repeat? (daily/weekly/monthly)",
+    ),
+    ("error.invalid_recurrence", "Invalid recurrence!"),
+    (
+        "status.template_saved",
+        "'{name}' saved as a template in `{path}`",
+    ),
+    ("error.save_template", "Saving the template failed: {error}"),
+    ("error.bulk_edit_dates", "Editing due dates failed: {error}"),
+    ("status.no_estimates", "No pending tasks have an estimate."),
+    ("status.forecast", "{date}: {name}"),
+    (
+        "status.forecast_late",
+        "{date}: {name} (after its due date!)",
+    ),
+    ("status.forecast_beyond", "Beyond range: {name}"),
+    (
+        "prompt.status",
+        "Set '{name}' to (todo/in progress/done, currently {current}):",
+    ),
+    ("error.invalid_status", "Invalid status!"),
+    ("status.surprise", "Why not work on this?"),
+    (
+        "prompt.month",
+        "Enter a month (YYYY-MM, leave blank for this month):",
+    ),
+    ("error.invalid_month", "Month must be in YYYY-MM format!"),
+    (
+        "prompt.confirm_reset",
+        "Mark all {count} task(s) as pending?",
+    ),
+    ("status.reset", "Reset {count} task(s) to pending"),
+    (
+        "prompt.switch_path",
+        "Enter the path of the tasks file to switch to (leave blank to stay on `{path}`):",
+    ),
+    (
+        "error.switch_read",
+        "Couldn't read `{new_path}`, staying on `{path}`: {error}",
+    ),
+    (
+        "error.switch_save",
+        "Saving to `{path}` failed, staying on it: {error}",
+    ),
+    ("status.saved", "Work saved to `{path}`"),
+    (
+        "status.switched",
+        "Switched to `{path}` with {count} task(s)",
+    ),
+    ("prompt.note", "Enter your note:"),
+    ("status.note_saved", "Note saved to `{path}`"),
+    ("error.save_note", "Saving the note failed: {error}"),
+    ("status.no_notes", "No notes yet."),
+    ("error.read_notes", "Couldn't read `{path}`: {error}"),
+    ("status.unfocused", "'{name}' removed from the focus list"),
+    ("status.focused", "'{name}' added to the focus list"),
+    ("error.save_focus", "Couldn't save the focus list: {error}"),
+    (
+        "status.focus_empty",
+        "The focus list is empty, add tasks to it with 'plan'.",
+    ),
+    ("status.snapshot_saved", "Snapshot saved to `{path}`"),
+    ("error.save_snapshot", "Saving the snapshot failed: {error}"),
+    ("status.no_matches", "No tasks match."),
+    (
+        "prompt.bulk_priority",
+        "Enter a priority for the {count} matching task(s) (low/medium/high):",
+    ),
+    (
+        "status.bulk_priority",
+        "Changed the priority of {count} task(s)",
+    ),
+    (
+        "status.sorting_off",
+        "Sorting turned off so the new order is kept",
+    ),
+    (
+        "status.ephemeral_discarded",
+        "Ephemeral session, changes discarded",
+    ),
+    ("status.archiving", "Archiving completed tasks..."),
+    ("status.archived", "Completed tasks moved to `{path}`"),
+    (
+        "error.archive",
+        "Archiving failed, keeping them in the list: {error}",
+    ),
+    ("error.save_tasks", "Saving to `{path}` failed: {error}"),
+    (
+        "prompt.save_path_or_discard",
+        "Enter another path to save to (leave blank to discard changes):",
+    ),
+    ("status.discarded", "Changes discarded"),
+    (
+        "error.save_session",
+        "Error: couldn't save view preferences ({error})",
+    ),
+    (
+        "error.save_history",
+        "Error: couldn't save input history ({error})",
+    ),
+    ("status.exiting", "Exiting successfully"),
+    ("error.done_usage", "usage: todolist done <task id>"),
+    ("prompt.confirm_save", "Save changes to `{path}`?"),
+    (
+        "error.read_archive",
+        "Couldn't read archived tasks: {error}",
+    ),
+    (
+        "error.csv_columns",
+        "Row {row}: expected 4 columns, found {found}",
+    ),
+    (
+        "error.csv_done",
+        "Row {row}: done must be 'true' or 'false', found '{found}'",
+    ),
+    (
+        "error.csv_due_date",
+        "Row {row}: due date must match {format}, found '{found}'",
+    ),
+    (
+        "error.read_translations",
+        "Warning: couldn't read translations from `{path}` ({error}), using English.",
+    ),
+    (
+        "error.read_config",
+        "Error: couldn't read `{path}` ({error}), using default settings.",
+    ),
+    (
+        "prompt.setup_welcome",
+        "Welcome! Let's set a few preferences (leave any blank to keep the default).",
+    ),
+    (
+        "prompt.setup_data_path",
+        "Where should tasks be saved? (default: {default})",
+    ),
+    (
+        "prompt.setup_date_format",
+        "Which date format should due dates be entered in? (default: {default})",
+    ),
+    (
+        "error.invalid_date_format",
+        "'{format}' isn't a usable date format, e.g. '%d/%m/%Y'.",
+    ),
+    (
+        "prompt.setup_confirm_deletes",
+        "Ask for confirmation before deleting tasks? (y/n, default: y)",
+    ),
+    ("status.setup_saved", "Preferences saved to `{path}`"),
+    (
+        "error.save_config",
+        "Error: couldn't save `{path}` ({error})",
+    ),
+    (
+        "error.not_writable",
+        "Warning: `{path}` cannot be written to ({error}), so your work will not be saved.",
+    ),
+    (
+        "prompt.save_path_or_continue",
+        "Enter another path to save to (leave blank to continue anyway):",
+    ),
+    ("prompt.new_name", "Enter a name for 'new_task':"),
+    ("prompt.new_desc", "Enter a short description for '{name}':"),
+    (
+        "prompt.remind_days",
+        "Enter how many days before the due date to be reminded (leave blank for default):",
+    ),
+    (
+        "prompt.weight",
+        "Enter a weight for '{name}' (leave blank for 1):",
+    ),
+    (
+        "prompt.estimate",
+        "Enter how many minutes '{name}' should take (leave blank for no estimate):",
+    ),
+    (
+        "prompt.priority",
+        "Enter a priority for '{name}' (low/medium/high, leave blank for medium):",
+    ),
+    (
+        "prompt.tags",
+        "Enter tags for '{name}', separated by commas (leave blank for none):",
+    ),
+    ("status.existing_tags", "Existing tags: {tags}"),
+    (
+        "prompt.link",
+        "Enter a link for '{name}', such as a ticket or doc URL (leave blank for none):",
+    ),
+    (
+        "error.invalid_link",
+        "Link must start with http:// or https://, or be left blank:",
+    ),
+    (
+        "prompt.use_known_tag",
+        "Use existing tag '{known}' for '{tag}'?",
+    ),
+    (
+        "prompt.filter_done",
+        "Show tasks that are (pending/done, leave blank for either):",
+    ),
+    ("prompt.filter_tag", "With tag (leave blank for any):"),
+    (
+        "prompt.filter_keyword",
+        "With a name or description containing (leave blank for any):",
+    ),
+    (
+        "prompt.filter_priority",
+        "With priority (low/medium/high, leave blank for any):",
+    ),
+    (
+        "prompt.filter_due_before",
+        "Due before ({format}, leave blank for any):",
+    ),
+    (
+        "prompt.edit_name",
+        "Enter a new name for '{name}' (leave blank to keep):",
+    ),
+    (
+        "prompt.edit_desc",
+        "Enter a new description (leave blank to keep):",
+    ),
+    (
+        "prompt.edit_due_date",
+        "Enter a new due date ({format}), 'none' to clear it, or leave blank to keep:",
+    ),
+    (
+        "error.edit_due_date",
+        "Date must match {format}, be 'none', or be left blank:",
+    ),
+    (
+        "prompt.edit_priority",
+        "Enter a new priority (low/medium/high, leave blank to keep):",
+    ),
+    (
+        "prompt.edit_estimate",
+        "Enter a new estimate in minutes (leave blank to keep):",
+    ),
+    ("status.updated", "'{name}' updated"),
+    ("error.write_audit_log", "Error: couldn't write to `{path}` ({error})"),
+    ("status.no_similar", "No similar tasks found."),
+    ("status.similar", "These tasks look alike:"),
+    ("prompt.merge_keep", "Enter the number of the task to keep, or leave blank to skip:"),
+    ("error.merge_not_in_group", "That task isn't in this group, skipping it."),
+    ("status.merged", "Merged {count} task(s) into '{name}'"),
+    ("status.now_recurring", "'{name}' now repeats {recurrence}, next due {date}"),
+    ("error.invalid_indexes", "Input must be valid indexes!"),
+    ("error.none_selected", "No tasks selected!"),
+    ("prompt.long_desc", "That description is {len} characters long (limit {max}).\n1. Keep it\n2. Truncate it\n3. Move it to the notes"),
+    ("error.due_date_required", "A due date is required, and must match {format}:"),
+    ("error.due_date", "Date must match {format}, or be left blank:"),
+    ("prompt.yes_no", "{question} (y/n)"),
+    ("status.footer", "Showing {shown} of {total} tasks ({pending} pending, {done} done)"),
+    ("status.no_completed", "No completed tasks yet."),
+    ("status.no_tasks_yet", "No tasks yet."),
+    ("status.untagged", "Untagged"),
+    ("status.tag_legend", "Tags: {tags}"),
+    ("status.done_suffix", " (done)"),
+    ("status.unscheduled", "Unscheduled / later"),
+    ("detail.name", "Name: {name} (#{id})"),
+    ("detail.description", "Description: {value}"),
+    ("detail.notes", "Notes: {value}"),
+    ("detail.due_date", "Due date: {value}"),
+    ("detail.status", "Status: {value}"),
+    ("detail.weight", "Weight: {value}"),
+    ("detail.priority", "Priority: {value}"),
+    ("detail.pinned", "Pinned"),
+    ("detail.repeats", "Repeats: {value}"),
+    ("detail.tags", "Tags: {value}"),
+    ("detail.blocked_by", "Blocked by: {value}"),
+    ("detail.remind", "Remind: {value} days before"),
+    ("detail.link", "Link: {value}"),
+    ("detail.time_estimated", "Time: {spent} spent of {estimate} estimated"),
+    ("detail.time", "Time: {value} spent"),
+    ("status.copied", "Task copied to clipboard"),
+    ("error.no_clipboard", "No clipboard available, copy the task manually:"),
+    ("status.now_priority", "'{name}' is now {priority} priority"),
+    ("status.line", "{pending} pending, {overdue} overdue"),
+    ("status.line_next", ", next: '{name}'"),
+    ("status.due_today", "due today"),
+    ("status.due_tomorrow", "due tomorrow"),
+    ("status.one_day_overdue", "1 day overdue"),
+    ("status.days_overdue", "{days} days overdue"),
+    ("status.due_in_days", "due in {days} days"),
+    ("prompt.over_limit", "You have {pending} pending tasks (limit {limit}). Add anyway?"),
+    ("prompt.over_limit_batch", "Adding {count} tasks would leave {total} pending (limit {limit}). Add them anyway?"),
+    ("prompt.reorder_from", "Enter the number of a task to move (leave blank when done, 'c' to cancel):"),
+    ("status.reorder_cancelled", "Reordering cancelled"),
+    ("prompt.reorder_to", "Move '{name}' to which position?"),
+    ("status.reorder_saved", "New order saved"),
+    ("error.dates_line_number", "Line {line}: doesn't start with a task number"),
+    ("error.dates_line_date", "Line {line}: '{found}' doesn't match {format}"),
+    ("status.dates_changed", "Changed the due date of {count} task(s)"),
+    ("status.banner_overdue", "OVERDUE: '{name}' was due {date}"),
+    ("status.banner_due_soon", "Due soon: '{name}' is due {date}"),
+    ("status.next_due", "'{name}' is next due {date}"),
+    ("prompt.review", "(k)eep, (c)omplete, (d)elete, (s)nooze {days} days, (e)dit, (q)uit, or Enter to skip:"),
+    ("status.reviewed", "Reviewed {count} task(s)"),
+    ("status.none_undated", "Every pending task has a due date."),
+    ("prompt.schedule", "Due date for '{name}' ({format}), (s)nooze {days} days, (q)uit, or Enter to skip:"),
+    ("error.schedule_input", "Date must match {format}, or enter s, q or nothing:"),
+    ("status.scheduled", "Scheduled {count} task(s)"),
+    ("status.snoozed", "'{name}' snoozed until {date}"),
+    ("status.now_status", "'{name}' is now {status}"),
+    ("status.completed_after", "Completed '{name}' after {duration}"),
+    ("duration.day", "{count} day"),
+    ("duration.days", "{count} days"),
+    ("duration.hour", "{count} hour"),
+    ("duration.hours", "{count} hours"),
+    ("duration.minute", "{count} minute"),
+    ("duration.minutes", "{count} minutes"),
+    ("duration.under_minute", "less than a minute"),
+    ("status.no_tracked", "No completed tasks have both an estimate and time logged."),
+    ("status.estimate_row", "{name}: {spent} spent of {estimate} estimated"),
+    ("status.estimate_summary", "Tasks took {percent}% of their estimated time, and {over} of {total} ran over."),
+    ("status.pruned_dep", "'{name}' was blocked by missing task #{id}"),
+    ("status.trashed", "'{name}' moved to the trash"),
+    ("status.restored", "'{name}' restored"),
+    ("report.heading", "Weekly report for the week of {week}"),
+    ("report.overdue", "WARNING: {count} task(s) overdue"),
+    ("report.overdue_task", "{name} (due {date})"),
+    ("report.pending", "Pending"),
+    ("report.nothing_pending", "Nothing pending."),
+    ("report.no_due_date", "No due date"),
+    ("report.completed", "Completed this week"),
+    ("report.nothing_completed", "Nothing completed yet."),
+    ("calendar.weekdays", "Mo Tu We Th Fr Sa Su"),
+    ("error.no_reference_date", "'{name}' has no valid due date to offset from!"),
+    ("error.offset_out_of_range", "{days} day(s) from {date} is past the last possible date!"),
+    ("editor.dates_header", "Edit the due dates ({format}) below, then save and close the editor.\nLeave a date blank to clear it. The names are only for reference."),
+    ("status.progress", "{done} of {total} tasks done ({percent}%)"),
+    ("status.progress_weighted", "Weighted completion: {percent}% ({done} of {total} weight)"),
+    ("error.invalid_json", "invalid JSON ({error})"),
+    ("error.invalid_toml", "invalid TOML ({error})"),
+    ("error.write_toml", "couldn't write TOML ({error})"),
+    ("error.parse", "line {line}: {message}"),
+    ("error.not_found", "there is no task number {num}"),
+    ("error.no_task_with_id", "there is no task with ID {id}"),
+    ("error.locked", "`{path}` is already open in another instance. If none is running, delete `{path}.lock`."),
+    ("error.lock_file", "couldn't create the lock file `{path}.lock` ({error})"),
+    ("error.unknown_fields", "`{path}` contains unexpected fields:\n\t{fields}"),
+    ("error.editor", "`{editor}` exited with {status}"),
+    ("error.unknown_field", "task {num}: '{field}'"),
+    ("error.remind_days", "That's too many days, enter a smaller number or leave it blank:"),
+    ("error.default_due_out_of_range", "The tag default of {days} days is too far away, so the due date was left blank."),
+    ("status.not_written", "`{path}` not written (ephemeral mode)"),
+    ("task_status.to_do", "to do"),
+    ("task_status.in_progress", "in progress"),
+    ("task_status.done", "done"),
+    ("priority.low", "low"),
+    ("priority.medium", "medium"),
+    ("priority.high", "high"),
+    ("recurrence.daily", "daily"),
+    ("recurrence.weekly", "weekly"),
+    ("recurrence.monthly", "monthly"),
+    ("detail.no_due_date", "no due date"),
+    ("error.snooze_out_of_range", "'{name}' can't be snoozed past the last date that can be shown."),
+    ("error.next_due_out_of_range", "'{name}' can't repeat after {date}, the last date that can be shown."),
+];
+
+// Format that due dates are stored in, regardless of the format they are entered in.
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+// Width assumed for output when the terminal's can't be detected, such as when piped.
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+
+// Layout of each task in the list view. The first line is the heading, which is colored
+// and fitted to the terminal; any further lines are printed beneath it.
+const DEFAULT_LINE_FORMAT: &str = "{number}. {status} {icon}{name} : {date}{tags}\n{desc}";
+
+// Fewest characters of a task name shown, however narrow the terminal.
+const MIN_NAME_WIDTH: usize = 10;
+
+// Columns a tab is assumed to take up when fitting the list view to the terminal.
+const TAB_WIDTH: usize = 8;
+
+// How many days before its due date a task is flagged, unless it sets its own lead time.
+const DUE_SOON_DAYS: i64 = 1;
+
 // Represents a task with its name, description, due date, and completion status.
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 struct Task {
     name: String,
     desc: String,
     due_date: String,
     done: bool,
+    #[serde(default)]
+    remind_before_days: Option<i64>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+    #[serde(default)]
+    priority: Priority,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    completed_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    id: u32,
+    #[serde(default)]
+    blocked_by: Vec<u32>,
+    #[serde(default)]
+    created_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    estimate_minutes: Option<u32>,
+    #[serde(default)]
+    time_spent_minutes: u32,
+    #[serde(default)]
+    link: Option<String>,
+    #[serde(default)]
+    in_progress: bool,
+}
+
+impl Default for Task {
+    fn default() -> Self {
+        Task {
+            name: String::new(),
+            desc: String::new(),
+            due_date: String::new(),
+            done: false,
+            remind_before_days: None,
+            weight: default_weight(),
+            priority: Priority::default(),
+            recurrence: None,
+            tags: vec![],
+            completed_at: None,
+            notes: String::new(),
+            pinned: false,
+            id: 0,
+            blocked_by: vec![],
+            created_at: None,
+            estimate_minutes: None,
+            time_spent_minutes: 0,
+            link: None,
+            in_progress: false,
+        }
+    }
+}
+
+impl Task {
+    // Combines the done and in-progress flags into the task's current state.
+    fn status(&self) -> Status {
+        if self.done {
+            Status::Done
+        } else if self.in_progress {
+            Status::InProgress
+        } else {
+            Status::Todo
+        }
+    }
+}
+
+// Where a task is in its workflow, from not yet started to finished.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Status::Todo => t("task_status.to_do"),
+            Status::InProgress => t("task_status.in_progress"),
+            Status::Done => t("task_status.done"),
+        };
+        write!(f, "{name}")
+    }
+}
+
+// How often a recurring task comes due again once completed.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+enum Recurrence {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Recurrence::Daily => t("recurrence.daily"),
+            Recurrence::Weekly => t("recurrence.weekly"),
+            Recurrence::Monthly => t("recurrence.monthly"),
+        };
+        write!(f, "{name}")
+    }
+}
+
+// Preset fields for a kind of task that gets created repeatedly, defined in `config.toml`.
+#[derive(Serialize, Deserialize, Clone)]
+struct Template {
+    name: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    priority: Priority,
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+}
+
+// Fields given to new tasks with a certain tag when they are left blank, defined in
+// `config.toml`.
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+struct TagDefaults {
+    #[serde(default)]
+    priority: Option<Priority>,
+    #[serde(default)]
+    due_in_days: Option<i64>,
+}
+
+// How important a task is, ordered from least to most urgent.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Priority::Low => t("priority.low"),
+            Priority::Medium => t("priority.medium"),
+            Priority::High => t("priority.high"),
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl Priority {
+    // Returns the next priority up, wrapping from high back around to low.
+    fn cycled(self) -> Priority {
+        match self {
+            Priority::Low => Priority::Medium,
+            Priority::Medium => Priority::High,
+            Priority::High => Priority::Low,
+        }
+    }
+}
+
+// Wrapper shape for task files that nest the task list under a `tasks` key.
+#[derive(Deserialize)]
+struct TaskFile {
+    tasks: Vec<Task>,
+}
+
+// A thought jotted down on the scratchpad, which isn't a task.
+#[derive(Serialize, Deserialize)]
+struct Note {
+    created_at: DateTime<Local>,
+    text: String,
+}
+
+// Weight given to tasks that don't specify one, so old files count each task equally.
+fn default_weight() -> u32 {
+    1
 }
 
-// Main program procedure
+// User settings loaded from `config.toml`. Missing keys fall back to their defaults.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+struct Config {
+    data_path: String,
+    trash_path: String,
+    archive_path: String,
+    date_format: String,
+    confirm_deletes: bool,
+    name_width: usize,
+    done_last: bool,
+    auto_sort: bool,
+    icons: IconSet,
+    color: bool,
+    bell_on_overdue: bool,
+    templates: Vec<Template>,
+    strict_load: bool,
+    backups: usize,
+    max_desc_length: usize,
+    recurrence_from: RecurrenceFrom,
+    audit_log: bool,
+    audit_log_path: String,
+    audit_log_max_entries: usize,
+    locale: String,
+    prune_deps_on_start: bool,
+    require_due_date: bool,
+    max_pending: Option<usize>,
+    tag_defaults: BTreeMap<String, TagDefaults>,
+    indent: String,
+    line_format: String,
+    default_sort: Option<SortKey>,
+    on_exit: OnExit,
+    done_marker: String,
+    pending_marker: String,
+    in_progress_marker: String,
+    daily_minutes: u32,
+    random_by_priority: bool,
+}
+
+// Which date a completed recurring task's next due date is counted from.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+enum RecurrenceFrom {
+    FromDue,
+    #[default]
+    FromToday,
+}
+
+// What happens to changes when the program is quit.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+enum OnExit {
+    #[default]
+    SaveSilently,
+    Prompt,
+    NeverSave,
+}
+
+// Terminal colors used to shade tasks by urgency.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Color {
+    Neutral,
+    Green,
+    Yellow,
+    Orange,
+    Red,
+    Cyan,
+}
+// Status icons shown beside each task in the list view.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+enum IconSet {
+    #[default]
+    Off,
+    Emoji,
+    Ascii,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_path: TASKS_PATH.to_string(),
+            trash_path: TRASH_PATH.to_string(),
+            archive_path: ARCHIVE_PATH.to_string(),
+            date_format: DATE_FORMAT.to_string(),
+            confirm_deletes: true,
+            name_width: 30,
+            done_last: false,
+            auto_sort: false,
+            icons: IconSet::Off,
+            color: false,
+            bell_on_overdue: false,
+            templates: vec![],
+            strict_load: false,
+            backups: 3,
+            max_desc_length: 200,
+            recurrence_from: RecurrenceFrom::FromToday,
+            audit_log: false,
+            audit_log_path: AUDIT_LOG_PATH.to_string(),
+            audit_log_max_entries: 1000,
+            locale: "en".to_string(),
+            prune_deps_on_start: false,
+            require_due_date: false,
+            max_pending: None,
+            tag_defaults: BTreeMap::new(),
+            indent: "\t".to_string(),
+            line_format: DEFAULT_LINE_FORMAT.to_string(),
+            default_sort: None,
+            on_exit: OnExit::SaveSilently,
+            done_marker: "[x]".to_string(),
+            pending_marker: "[ ]".to_string(),
+            in_progress_marker: "[~]".to_string(),
+            daily_minutes: 240,
+            random_by_priority: false,
+        }
+    }
+}
+
+// Fields the task list can be sorted by.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Name,
+    DueDate,
+    Priority,
+}
+
+// View preferences saved on change and restored at startup.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct SessionState {
+    sort_key: Option<SortKey>,
+    reverse: bool,
+    show_blocked: bool,
+    // IDs of the tasks picked for the focus list.
+    focus: HashSet<u32>,
+    // Filter kept on the task list until it is cleared.
+    filter: Option<Filter>,
+}
+
+// Period that completions are grouped into for the progress report.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Bucket {
+    Day,
+    Week,
+}
+
+// Conditions a task must meet to be shown by the filter view. `None` ignores that field.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Filter {
+    done: Option<bool>,
+    tag: Option<String>,
+    keyword: Option<String>,
+    priority: Option<Priority>,
+    due_before: Option<NaiveDate>,
+}
+
+impl Filter {
+    // Checks whether no condition is set, so every task matches.
+    fn is_empty(&self) -> bool {
+        self.done.is_none()
+            && self.tag.is_none()
+            && self.keyword.is_none()
+            && self.priority.is_none()
+            && self.due_before.is_none()
+    }
+
+    // Checks whether a task meets every condition that is set.
+    fn matches(&self, task: &Task) -> bool {
+        self.done.is_none_or(|done| task.done == done)
+            && self.tag.as_ref().is_none_or(|tag| {
+                task.tags
+                    .iter()
+                    .any(|existing| existing.eq_ignore_ascii_case(tag))
+            })
+            && self.keyword.as_ref().is_none_or(|keyword| {
+                let keyword = keyword.to_lowercase();
+                task.name.to_lowercase().contains(&keyword)
+                    || task.desc.to_lowercase().contains(&keyword)
+            })
+            && self
+                .priority
+                .is_none_or(|priority| task.priority == priority)
+            && self
+                .due_before
+                .is_none_or(|before| parse_due_date(task).is_some_and(|due| due < before))
+    }
+}
+
+// Location and size limit of the audit log.
+struct AuditLog {
+    path: String,
+    max_entries: usize,
+}
+
+// Everything that can go wrong reading, writing or changing the task list and the files
+// kept beside it. Shown to the user through its `Display` messages.
+#[derive(Debug, thiserror::Error)]
+enum TodoError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    #[error("{}", t_fill("error.invalid_json", &[("error", &.0.to_string())]))]
+    Json(#[from] serde_json::Error),
+    #[error("{}", t_fill("error.invalid_toml", &[("error", &.0.to_string())]))]
+    TomlRead(#[from] toml::de::Error),
+    #[error("{}", t_fill("error.write_toml", &[("error", &.0.to_string())]))]
+    TomlWrite(#[from] toml::ser::Error),
+    #[error("{}", t_fill("error.parse", &[("line", &.line.to_string()), ("message", .message)]))]
+    Parse { line: usize, message: String },
+    #[error("{}", t_fill("error.not_found", &[("num", &(.0 + 1).to_string())]))]
+    NotFound(usize),
+    #[error("{}", t_fill("error.no_task_with_id", &[("id", &.0.to_string())]))]
+    NoTaskWithId(u32),
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("{}", t_fill("error.locked", &[("path", .0)]))]
+    Locked(String),
+    #[error("{}", t_fill("error.lock_file", &[("path", .path), ("error", &.source.to_string())]))]
+    LockFile { path: String, source: io::Error },
+    #[error("{}", t_fill("error.unknown_fields", &[("path", .path), ("fields", &.fields.join("\n\t"))]))]
+    UnknownFields { path: String, fields: Vec<String> },
+    #[error("{}", t_fill("error.editor", &[("editor", .editor), ("status", &.status.to_string())]))]
+    Editor {
+        editor: String,
+        status: std::process::ExitStatus,
+    },
+}
+
+impl TodoError {
+    // The process exit code for this error, documented in the README for scripts.
+    fn exit_code(&self) -> i32 {
+        match self {
+            TodoError::InvalidInput(_) => EXIT_INVALID_INPUT,
+            TodoError::NotFound(_) | TodoError::NoTaskWithId(_) => EXIT_NOT_FOUND,
+            _ => EXIT_FILE_ERROR,
+        }
+    }
+
+    // Checks whether this is an IO error of the given kind, such as a missing file.
+    fn is_io(&self, kind: io::ErrorKind) -> bool {
+        matches!(self, TodoError::Io(error) if error.kind() == kind)
+    }
+}
+
+type Result<T> = std::result::Result<T, TodoError>;
+
+// Marks a tasks file as in use by creating `<path>.lock`, which is removed when dropped.
+struct FileLock {
+    path: String,
+}
+
+impl FileLock {
+    // Creates the lock file, failing with `AlreadyExists` if another instance holds it.
+    // A lock left behind by a process that is no longer running, such as one killed by
+    // Ctrl-C, is taken over.
+    fn acquire(path: &str) -> io::Result<FileLock> {
+        let path = format!("{path}.lock");
+        let open = || OpenOptions::new().write(true).create_new(true).open(&path);
+        let mut file = match open() {
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists && is_stale_lock(&path) => {
+                fs::remove_file(&path)?;
+                open()?
+            }
+            result => result?,
+        };
+        writeln!(file, "{}", std::process::id())?;
+        Ok(FileLock { path })
+    }
+}
+
+// Checks whether the lock file at `path` names a process that has since exited. Locks
+// whose process can't be read or checked are assumed to still be held.
+fn is_stale_lock(path: &str) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| contents.trim().parse::<u32>().ok())
+        .is_some_and(|pid| !process_alive(pid))
+}
+
+// Checks whether a process with the given ID is running, assuming it is if that can't be
+// determined.
+fn process_alive(pid: u32) -> bool {
+    if cfg!(windows) {
+        let filter = format!("PID eq {pid}");
+        return std::process::Command::new("tasklist")
+            .args(["/FI", &filter, "/NH"])
+            .output()
+            .map_or(true, |output| {
+                String::from_utf8_lossy(&output.stdout).contains(&pid.to_string())
+            });
+    }
+
+    if Path::new("/proc/self").exists() {
+        return Path::new(&format!("/proc/{pid}")).exists();
+    }
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map_or(true, |status| status.success())
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+// Actions offered by the main menu.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MenuChoice {
+    ViewTasks,
+    AddTask,
+    CompleteTask,
+    DeleteTask,
+    ToggleByName,
+    ShowProgress,
+    ViewDetails,
+    NextTask,
+    RestoreTask,
+    EmptyTrash,
+    SortTasks,
+    RelativeDueDate,
+    CopyTask,
+    NewFromTemplate,
+    BulkTag,
+    WeekAgenda,
+    EditTask,
+    CompletionReport,
+    FilterTasks,
+    TogglePin,
+    AddDependency,
+    RescheduleOverdue,
+    ViewByTag,
+    CompletedToday,
+    ReverseOrder,
+    ToggleBlocked,
+    ExportCsv,
+    ImportCsv,
+    SplitTask,
+    LogTime,
+    EstimateReport,
+    PruneDependencies,
+    Review,
+    SetPriority,
+    WeeklyReport,
+    ShiftAll,
+    OpenLink,
+    MakeRepeatable,
+    BulkEditDates,
+    Forecast,
+    SetStatus,
+    SurpriseMe,
+    Calendar,
+    ResetAll,
+    SwitchFile,
+    GroupByTag,
+    AddNote,
+    ViewNotes,
+    ScheduleUndated,
+    ToggleFocus,
+    ViewFocus,
+    MergeDuplicates,
+    Snapshot,
+    BulkPriority,
+    Reorder,
+    Quit,
+}
+
+// Menu entries in the order they are listed and numbered, with the `t` key of each label.
+const MENU: &[(MenuChoice, &str)] = &[
+    (MenuChoice::ViewTasks, "menu.view_tasks"),
+    (MenuChoice::AddTask, "menu.add_task"),
+    (MenuChoice::CompleteTask, "menu.complete_task"),
+    (MenuChoice::DeleteTask, "menu.delete_task"),
+    (MenuChoice::ToggleByName, "menu.toggle_by_name"),
+    (MenuChoice::ShowProgress, "menu.show_progress"),
+    (MenuChoice::ViewDetails, "menu.view_details"),
+    (MenuChoice::NextTask, "menu.next_task"),
+    (MenuChoice::RestoreTask, "menu.restore_task"),
+    (MenuChoice::EmptyTrash, "menu.empty_trash"),
+    (MenuChoice::SortTasks, "menu.sort_tasks"),
+    (MenuChoice::RelativeDueDate, "menu.relative_due_date"),
+    (MenuChoice::CopyTask, "menu.copy_task"),
+    (MenuChoice::NewFromTemplate, "menu.new_from_template"),
+    (MenuChoice::BulkTag, "menu.bulk_tag"),
+    (MenuChoice::WeekAgenda, "menu.week_agenda"),
+    (MenuChoice::EditTask, "menu.edit_task"),
+    (MenuChoice::CompletionReport, "menu.completion_report"),
+    (MenuChoice::FilterTasks, "menu.filter_tasks"),
+    (MenuChoice::TogglePin, "menu.toggle_pin"),
+    (MenuChoice::AddDependency, "menu.add_dependency"),
+    (MenuChoice::RescheduleOverdue, "menu.reschedule_overdue"),
+    (MenuChoice::ViewByTag, "menu.view_by_tag"),
+    (MenuChoice::CompletedToday, "menu.completed_today"),
+    (MenuChoice::ReverseOrder, "menu.reverse_order"),
+    (MenuChoice::ToggleBlocked, "menu.toggle_blocked"),
+    (MenuChoice::ExportCsv, "menu.export_csv"),
+    (MenuChoice::ImportCsv, "menu.import_csv"),
+    (MenuChoice::SplitTask, "menu.split_task"),
+    (MenuChoice::LogTime, "menu.log_time"),
+    (MenuChoice::EstimateReport, "menu.estimate_report"),
+    (MenuChoice::PruneDependencies, "menu.prune_dependencies"),
+    (MenuChoice::Review, "menu.review"),
+    (MenuChoice::SetPriority, "menu.set_priority"),
+    (MenuChoice::WeeklyReport, "menu.weekly_report"),
+    (MenuChoice::ShiftAll, "menu.shift_all"),
+    (MenuChoice::OpenLink, "menu.open_link"),
+    (MenuChoice::MakeRepeatable, "menu.make_repeatable"),
+    (MenuChoice::BulkEditDates, "menu.bulk_edit_dates"),
+    (MenuChoice::Forecast, "menu.forecast"),
+    (MenuChoice::SetStatus, "menu.set_status"),
+    (MenuChoice::SurpriseMe, "menu.surprise_me"),
+    (MenuChoice::Calendar, "menu.calendar"),
+    (MenuChoice::ResetAll, "menu.reset_all"),
+    (MenuChoice::SwitchFile, "menu.switch_file"),
+    (MenuChoice::GroupByTag, "menu.group_by_tag"),
+    (MenuChoice::AddNote, "menu.add_note"),
+    (MenuChoice::ViewNotes, "menu.view_notes"),
+    (MenuChoice::ScheduleUndated, "menu.schedule_undated"),
+    (MenuChoice::ToggleFocus, "menu.toggle_focus"),
+    (MenuChoice::ViewFocus, "menu.view_focus"),
+    (MenuChoice::MergeDuplicates, "menu.merge_duplicates"),
+    (MenuChoice::Snapshot, "menu.snapshot"),
+    (MenuChoice::BulkPriority, "menu.bulk_priority"),
+    (MenuChoice::Reorder, "menu.reorder"),
+    (MenuChoice::Quit, "menu.quit"),
+];
+
+// Summary counts used to report progress through the task list. Weights are summed as
+// `u64` so that however large each task's `u32` weight is, the totals can't overflow.
+struct Stats {
+    total: usize,
+    done: usize,
+    total_weight: u64,
+    done_weight: u64,
+}
+
+// Main program procedure, exiting with a code that tells scripts what went wrong.
 fn main() {
-    // Initializes vector of tasks, and copies data from `tasks.json` if file exists.
-    let mut tasks: Vec<Task> = match read_tasks() {
+    if let Err(error) = run() {
+        eprintln!(
+            "{}",
+            t_fill("error.message", &[("error", &error.to_string())])
+        );
+        std::process::exit(error.exit_code());
+    }
+}
+
+// Runs a command-line flag or subcommand, or the interactive menu.
+fn run() -> Result<()> {
+    // Flags apply to subcommands too, so `--ephemeral` is read before anything else runs.
+    let args: Vec<String> = env::args().skip(1).collect();
+    let ephemeral = args.iter().any(|arg| arg == "--ephemeral");
+    if ephemeral {
+        EPHEMERAL.store(true, AtomicOrdering::Relaxed);
+    }
+    let words: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| !arg.starts_with("--"))
+        .collect();
+
+    // Handles command-line flags that bypass the interactive menu.
+    if args.iter().any(|arg| arg == "--json") {
+        let config = load_config(false);
+        print_tasks_json(&read_tasks_for_script(&config)?);
+        return Ok(());
+    }
+    if args.iter().any(|arg| arg == "--show-config") {
+        // Shows the settings actually in effect, after defaults and environment overrides.
+        print!("{}", toml::to_string_pretty(&load_config(false))?);
+        return Ok(());
+    }
+    if words.first() == Some(&"status") {
+        let config = load_config(false);
+        init_translations(&config.locale);
+        println!("{}", status_line(&read_tasks_or_empty(&config.data_path)?));
+        return Ok(());
+    }
+    if words.first() == Some(&"done") {
+        return complete_by_id(words.get(1).copied());
+    }
+    if let Some(arg) = args.iter().find(|arg| arg.starts_with("--count")) {
+        let config = load_config(false);
+        init_translations(&config.locale);
+        let tasks = read_tasks_or_empty(&config.data_path)?;
+        let today = Local::now().date_naive();
+        match arg.as_str() {
+            "--count" => println!("{}", tasks.iter().filter(|task| !task.done).count()),
+            "--count=overdue" => println!(
+                "{}",
+                tasks.iter().filter(|task| is_overdue(task, today)).count()
+            ),
+            _ => {
+                return Err(TodoError::InvalidInput(t_fill(
+                    "error.unknown_count_option",
+                    &[("option", arg)],
+                )))
+            }
+        }
+        return Ok(());
+    }
+
+    if ephemeral {
+        println!("{}", t("status.ephemeral"));
+    }
+
+    // First-run setup is skipped in ephemeral mode, as its answers couldn't be saved.
+    let mut config = load_config(!writes_disabled());
+    init_translations(&config.locale);
+
+    // Warns up front if the tasks file can't be written, rather than failing at exit.
+    let mut path: String = if writes_disabled() {
+        config.data_path.clone()
+    } else {
+        ensure_writable(config.data_path.clone())
+    };
+
+    // Refuses to start if another instance already has the chosen tasks file open. A path
+    // the user continued with despite it being unwritable can't be locked or saved to.
+    let mut _lock = if writes_disabled() || check_writable(&path).is_err() {
+        None
+    } else {
+        Some(lock_tasks_file(&path)?)
+    };
+
+    // In strict mode, refuses to start rather than silently dropping unrecognized fields.
+    if config.strict_load {
+        match find_unknown_fields(&config.data_path) {
+            Ok(unknown) if !unknown.is_empty() => {
+                return Err(TodoError::UnknownFields {
+                    path: config.data_path.clone(),
+                    fields: unknown,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    // Initializes vector of tasks, and copies data from the tasks file if it exists.
+    let mut tasks: Vec<Task> = match read_tasks(&config.data_path) {
         Ok(tasks) => {
-            println!("loaded tasks from `tasks.json`");
+            println!(
+                "{}",
+                t_fill("status.loaded", &[("path", &config.data_path)])
+            );
             tasks
         }
-        Err(_) => {
-            println!("`tasks.json` is empty, no tasks loaded.");
+        // A missing or blank file is a fresh list rather than a problem worth reporting.
+        Err(error) if error.is_io(io::ErrorKind::NotFound) || is_blank_file(&config.data_path) => {
+            println!(
+                "{}",
+                t_fill("status.empty_file", &[("path", &config.data_path)])
+            );
+            vec![]
+        }
+        Err(error) => {
+            let values = [
+                ("path", config.data_path.as_str()),
+                ("error", &error.to_string()),
+            ];
+            println!("{}", t_fill("error.read_tasks", &values));
+            println!("{}", t("status.no_tasks_loaded"));
             vec![]
         }
     };
 
+    init_audit_log(&config);
+    reserve_retired_ids(&config);
+
+    assign_missing_ids(&mut tasks);
+    let deduped = dedupe_ids(&mut tasks);
+    if deduped > 0 {
+        let count = deduped.to_string();
+        println!("{}", t_fill("status.deduped_ids", &[("count", &count)]));
+    }
+    if config.prune_deps_on_start {
+        let pruned = prune_orphan_deps(&mut tasks);
+        if pruned > 0 {
+            let count = pruned.to_string();
+            println!("{}", t_fill("status.pruned_on_start", &[("count", &count)]));
+        }
+    }
+    print_due_banner(&tasks, Local::now().date_naive(), config.bell_on_overdue);
+
+    // Restores the sort order last chosen, falling back to the configured default sort.
+    let mut session: SessionState = read_session(SESSION_PATH);
+    if session.sort_key.is_none() {
+        session.sort_key = config.default_sort;
+    }
+    sort_tasks(&mut tasks, &session, &config);
+
+    // Line editing and history for the menu prompt, only when a person is typing.
+    let mut editor: Option<DefaultEditor> = open_editor();
+
     // Runtime loop
     loop {
-        println!(
-            "\nWhat would you like to? (eg: '1')\n1. View tasks\n2. Add a task\n3. Complete task\n4. Delete task"
-        );
-        let resp = read_line();
+        print_menu();
+        let choice = match read_menu_input(&mut editor) {
+            // Running out of input, such as Ctrl-D or the end of a piped script, quits.
+            None => Some(MenuChoice::Quit),
+            // A bare Enter just shows the menu again.
+            Some(resp) if resp.is_empty() => continue,
+            Some(resp) => parse_menu_choice(&resp),
+        };
 
-        match resp.as_str() {
-            "1" => view_tasks(&mut tasks),
+        match choice {
+            Some(MenuChoice::ViewTasks) => {
+                partition_tasks(&mut tasks, &config);
+                print_tag_legend(&tag_counts(&tasks));
+                view_tasks_where(&tasks, &config, |task| {
+                    (session.show_blocked || is_ready(task, &tasks))
+                        && session
+                            .filter
+                            .as_ref()
+                            .is_none_or(|filter| filter.matches(task))
+                });
+                if session.filter.is_some() {
+                    println!("\t{}", t("status.filter_on"));
+                }
+            }
 
-            "2" => {
-                let new_task = create_task();
+            Some(MenuChoice::AddTask) => {
+                if !allow_another_task(&tasks, &config) {
+                    continue;
+                }
+                let known_tags: Vec<String> = tag_counts(&tasks).into_keys().collect();
+                let new_task = create_task(&config, &known_tags);
                 add_task(&mut tasks, new_task);
+                auto_sort(&mut tasks, &session, &config);
             }
 
-            "3" => {
-                view_tasks(&tasks);
+            Some(MenuChoice::CompleteTask) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
 
-                println!("\nSelect a task to mark as complete:");
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_complete"));
 
                 if let Some(index) = read_index_input(&tasks) {
-                    complete_task(&mut tasks, index);
+                    // Estimated tasks with no time logged get a chance to record it.
+                    if tasks[index].estimate_minutes.is_some()
+                        && tasks[index].time_spent_minutes == 0
+                    {
+                        let name = tasks[index].name.as_str();
+                        println!("\n{}", t_fill("prompt.minutes_taken", &[("name", name)]));
+                        if let Ok(minutes) = read_line().parse::<u32>() {
+                            log_time(&mut tasks[index], minutes);
+                        }
+                    }
+                    complete_task(&mut tasks, index, config.recurrence_from);
+                    auto_sort(&mut tasks, &session, &config);
                 } else {
                     continue;
                 }
             }
 
-            "4" => {
-                view_tasks(&tasks);
+            Some(MenuChoice::DeleteTask) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
 
-                println!("\nSelect a task to delete:");
+                println!("\n{}", t("prompt.select_delete"));
 
                 if let Some(index) = read_index_input(&tasks) {
-                    delete_task(&mut tasks, index);
+                    let prompt = t_fill("prompt.confirm_delete", &[("name", &tasks[index].name)]);
+                    if !config.confirm_deletes || confirm(&prompt) {
+                        if let Err(error) = trash_task(&mut tasks, index, &config.trash_path) {
+                            println!(
+                                "\n{}",
+                                t_fill("error.trash_task", &[("error", &error.to_string())])
+                            );
+                        }
+                        auto_sort(&mut tasks, &session, &config);
+                    }
                 } else {
                     continue;
                 }
             }
 
-            // If other input, save task vector to `tasks.json` and exit program.
-            _ => {
-                println!("\nRemoving completed tasks...");
-                remove_complete_tasks(&mut tasks);
-                println!("Completed tasks removed");
+            Some(MenuChoice::ToggleByName) => {
+                println!("\n{}", t("prompt.toggle_name"));
+                let name = read_line();
+
+                if toggle_done_by_name(&mut tasks, &name) {
+                    auto_sort(&mut tasks, &session, &config);
+                } else {
+                    println!("\n{}", t_fill("error.no_name_match", &[("name", &name)]));
+                }
+            }
+
+            Some(MenuChoice::ShowProgress) => print_stats(&compute_stats(&tasks)),
 
-                println!("\nSerializing data...");
-                let serialized_tasks = serde_json::to_string(&tasks).expect("Serialization failed");
-                println!("Data serialized");
+            Some(MenuChoice::ViewDetails) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
 
-                println!("\nCreating file...");
-                let mut file = File::create("tasks.json").expect("File creation failed");
-                println!("File created");
+                view_tasks(&tasks, &config);
 
-                println!("\nSaving work...");
-                file.write_all(serialized_tasks.as_bytes())
-                    .expect("Writing to file failed");
-                println!("Work saved");
+                println!("\n{}", t("prompt.select_view"));
 
-                println!("\nExiting successfully");
-                break;
+                if let Some(index) = read_index_input(&tasks) {
+                    view_task_details(&tasks[index]);
+                }
             }
-        }
-    }
-}
 
-// Removes all completed tasks from tasks vector.
-fn remove_complete_tasks(tasks: &mut Vec<Task>){
-    tasks.retain(|task| !task.done);
-}
+            Some(MenuChoice::NextTask) => match pick_next(&tasks, Local::now().date_naive()) {
+                Some(task) => {
+                    println!("\n{}", t("status.next_up"));
+                    view_task_details(task);
+                }
+                None => println!("\n{}", t("status.all_done")),
+            },
 
-// Reads tasks from the "tasks.json" file and returns them as a vector.
-fn read_tasks() -> Result<Vec<Task>> {
-    let file = File::open("tasks.json")?;
-    let reader = BufReader::new(file);
-    let tasks: Vec<Task> = serde_json::from_reader(reader)?;
-    Ok(tasks)
-}
+            Some(MenuChoice::RestoreTask) => {
+                let trash = match read_tasks_or_empty(&config.trash_path) {
+                    Ok(trash) => trash,
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.read_trash", &[("error", &error)]));
+                        continue;
+                    }
+                };
+                if trash.is_empty() {
+                    println!("\n{}", t("status.trash_empty"));
+                    continue;
+                }
 
-// Adds a new task to the vector of tasks.
-fn add_task(tasks: &mut Vec<Task>, new_task: Task) {
-    tasks.push(new_task);
-}
+                view_tasks(&trash, &config);
 
-// Creates a new task by prompting the user for its name, description, and due date.
-fn create_task() -> Task {
-    println!("\nEnter a name for 'new_task':");
-    let name: String = read_line();
+                println!("\n{}", t("prompt.select_restore"));
 
-    println!("\nEnter a short description for '{name}':");
-    let desc: String = read_line();
+                if let Some(index) = read_index_input(&trash) {
+                    if let Err(error) = restore_task(&mut tasks, index, &config.trash_path) {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.restore_task", &[("error", &error)]));
+                    }
+                    auto_sort(&mut tasks, &session, &config);
+                }
+            }
 
-    println!("\nEnter a due date for '{name}':");
-    let due_date: String = read_line();
+            Some(MenuChoice::EmptyTrash) => {
+                if !config.confirm_deletes || confirm(t("prompt.confirm_empty_trash")) {
+                    match purge_trash(&config.trash_path) {
+                        Ok(()) => println!("\n{}", t("status.trash_emptied")),
+                        Err(error) => {
+                            let error = error.to_string();
+                            println!("\n{}", t_fill("error.empty_trash", &[("error", &error)]));
+                        }
+                    }
+                }
+            }
 
-    let done: bool = false;
+            Some(MenuChoice::SortTasks) => {
+                println!("\n{}", t("prompt.sort_by"));
+                match parse_sort_key(&read_line()) {
+                    Some(key) => {
+                        session.sort_key = Some(key);
+                        if let Err(error) = write_session(&session, SESSION_PATH) {
+                            let error = error.to_string();
+                            println!("\n{}", t_fill("error.save_sort", &[("error", &error)]));
+                        }
+                        sort_tasks(&mut tasks, &session, &config);
+                        view_tasks(&tasks, &config);
+                    }
+                    None => println!("\n{}", t("error.invalid_sort")),
+                }
+            }
 
-    Task {
-        name,
-        desc,
-        due_date,
-        done,
-    }
-}
-// Reads user input for the task index and returns it as an `Option<usize>`.
-// If the input is invalid or out of range, it returns `None`.
-fn read_index_input(tasks: &[Task]) -> Option<usize> {
-    let index: usize = match read_line().parse::<usize>() {
-        Ok(num) => num - 1,
-        Err(_) => {
-            println!("\nInput must be a valid index!");
-            return None;
-        }
-    };
+            Some(MenuChoice::RelativeDueDate) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
 
-    if index >= tasks.len() {
-        println!("\nInvalid task index!");
-        return None;
-    } else {
-        return Some(index);
-    }
-}
+                view_tasks(&tasks, &config);
 
-// Reads a line of input from the user.
-fn read_line() -> String {
-    let mut input: String = String::new();
-    match stdin().read_line(&mut input) {
-        Ok(_) => {
-            input = input.trim().to_string();
-        }
-        Err(error) => {
-            eprintln!("Error: {}", error);
-        }
-    }
+                println!("\n{}", t("prompt.select_reschedule"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
 
-    input
-}
+                println!("\n{}", t("prompt.select_relative_to"));
+                let Some(reference) = read_index_input(&tasks) else {
+                    continue;
+                };
 
-// Displays the list of tasks to the user.
-fn view_tasks(tasks: &Vec<Task>) {
-    println!(); // newline
+                println!("\n{}", t("prompt.relative_offset"));
+                let Ok(offset_days) = read_line().parse::<i64>() else {
+                    println!("\n{}", t("error.offset_days"));
+                    continue;
+                };
 
-    for (i, task) in tasks.iter().enumerate() {
-        println!(
-            "\t{}. {} : {} : Done - {}\n\t{}\n",
-            i + 1,
-            task.name,
-            task.due_date,
-            task.done,
-            task.desc
-        );
-    }
-}
+                match set_relative_due_date(&mut tasks, index, reference, offset_days) {
+                    Ok(()) => {
+                        let date = display_date(&tasks[index].due_date, &config.date_format);
+                        let values = [("name", tasks[index].name.as_str()), ("date", &date)];
+                        println!("\n{}", t_fill("status.now_due", &values));
+                        auto_sort(&mut tasks, &session, &config);
+                    }
+                    Err(message) => println!("\n{message}"),
+                }
+            }
 
-// Marks a task as complete at the specified index.
-fn complete_task(tasks: &mut Vec<Task>, index: usize) {
-    if let Some(task) = tasks.get_mut(index) {
-        task.done = true;
-    } else {
-        println!("\nInvalid task index!");
-    }
-}
+            Some(MenuChoice::CopyTask) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
 
-// Deletes a task at the specified index.
-fn delete_task(tasks: &mut Vec<Task>, index: usize) {
-    if let Some(_) = tasks.get(index) {
-        tasks.remove(index);
-    } else {
-        println!("\nInvalid task index!");
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_copy"));
+
+                if let Some(index) = read_index_input(&tasks) {
+                    copy_to_clipboard(&format_task_for_clipboard(&tasks[index]));
+                }
+            }
+
+            Some(MenuChoice::NewFromTemplate) => {
+                if config.templates.is_empty() {
+                    println!(
+                        "\n{}",
+                        t_fill("error.no_templates", &[("path", CONFIG_PATH)])
+                    );
+                    continue;
+                }
+
+                println!(); // newline
+                for (i, template) in config.templates.iter().enumerate() {
+                    println!("\t{}. {}", i + 1, template.name);
+                }
+
+                println!("\n{}", t("prompt.select_template"));
+                let Some(template) = read_line()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|num| config.templates.get(num.wrapping_sub(1)))
+                else {
+                    println!("\n{}", t("error.invalid_template"));
+                    continue;
+                };
+
+                if !allow_another_task(&tasks, &config) {
+                    continue;
+                }
+
+                let mut new_task = task_from_template(template);
+                let values = [
+                    ("name", new_task.name.as_str()),
+                    ("format", &config.date_format),
+                ];
+                println!("\n{}", t_fill("prompt.due_date", &values));
+                new_task.due_date = read_date(&config.date_format, config.require_due_date);
+                add_task(&mut tasks, new_task);
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::BulkTag) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_bulk_tag"));
+                let Some(indices) = read_indices_input(&tasks) else {
+                    continue;
+                };
+
+                println!("\n{}", t("prompt.tag_to_add"));
+                let tag = read_line();
+                if tag.is_empty() {
+                    println!("\n{}", t("error.tag_blank"));
+                    continue;
+                }
+
+                let updated = add_tag_to_tasks(&mut tasks, &indices, &tag);
+                let count = updated.to_string();
+                let values = [("count", count.as_str()), ("tag", &tag)];
+                println!("\n{}", t_fill("status.tagged", &values));
+            }
+
+            Some(MenuChoice::WeekAgenda) => {
+                let today = Local::now().date_naive();
+                let week_start =
+                    today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+                view_week(&tasks, week_start, &config);
+            }
+
+            Some(MenuChoice::EditTask) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_edit"));
+
+                if let Some(index) = read_index_input(&tasks) {
+                    edit_task(&mut tasks[index], &config);
+                    auto_sort(&mut tasks, &session, &config);
+                }
+            }
+
+            Some(MenuChoice::CompletionReport) => {
+                println!("\n{}", t("prompt.group_completions"));
+                let bucket = match read_line().to_lowercase().as_str() {
+                    "1" | "day" => Bucket::Day,
+                    "2" | "week" => Bucket::Week,
+                    _ => {
+                        println!("\n{}", t("error.invalid_option"));
+                        continue;
+                    }
+                };
+
+                let history = with_archive(&tasks, &config.archive_path);
+                print_histogram(&completion_histogram(&history, bucket));
+            }
+
+            Some(MenuChoice::FilterTasks) => {
+                let filter = read_filter(&config);
+                if filter.is_empty() {
+                    if session.filter.take().is_some() {
+                        println!("\n{}", t("status.filter_cleared"));
+                        if let Err(error) = write_session(&session, SESSION_PATH) {
+                            println!(
+                                "\n{}",
+                                t_fill("error.save_view", &[("error", &error.to_string())])
+                            );
+                        }
+                    }
+                    view_tasks(&tasks, &config);
+                    continue;
+                }
+
+                view_tasks_where(&tasks, &config, |task| filter.matches(task));
+                if confirm(t("prompt.keep_filter")) {
+                    session.filter = Some(filter);
+                    if let Err(error) = write_session(&session, SESSION_PATH) {
+                        println!(
+                            "\n{}",
+                            t_fill("error.save_view", &[("error", &error.to_string())])
+                        );
+                    }
+                }
+            }
+
+            Some(MenuChoice::TogglePin) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_pin"));
+
+                if let Some(index) = read_index_input(&tasks) {
+                    let task = &mut tasks[index];
+                    task.pinned = !task.pinned;
+                    log_action(&format!("set '{}' pinned - {}", task.name, task.pinned));
+                    let key = if task.pinned {
+                        "status.pinned"
+                    } else {
+                        "status.unpinned"
+                    };
+                    println!("\n{}", t_fill(key, &[("name", &task.name)]));
+                    auto_sort(&mut tasks, &session, &config);
+                }
+            }
+
+            Some(MenuChoice::AddDependency) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_blocked"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                println!("\n{}", t("prompt.select_blocker"));
+                let Some(blocker) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                let (from, to) = (tasks[index].id, tasks[blocker].id);
+                let (name, blocker_name) = (tasks[index].name.clone(), tasks[blocker].name.clone());
+                let names = [("name", name.as_str()), ("blocker", &blocker_name)];
+                if would_create_cycle(&tasks, from, to) {
+                    println!("\n{}", t_fill("error.dependency_cycle", &names));
+                } else if !tasks[index].blocked_by.contains(&to) {
+                    tasks[index].blocked_by.push(to);
+                    log_action(&format!(
+                        "made '{}' blocked by '{}'",
+                        tasks[index].name, tasks[blocker].name
+                    ));
+                    println!("\n{}", t_fill("status.now_blocked", &names));
+                }
+            }
+
+            Some(MenuChoice::RescheduleOverdue) => {
+                let today = Local::now().date_naive();
+                let overdue = tasks.iter().filter(|task| is_overdue(task, today)).count();
+                if overdue == 0 {
+                    println!("\n{}", t("status.no_overdue"));
+                    continue;
+                }
+
+                let count = overdue.to_string();
+                if confirm(&t_fill("prompt.confirm_reschedule", &[("count", &count)])) {
+                    let changed = reschedule_overdue(&mut tasks, today).to_string();
+                    println!("\n{}", t_fill("status.rescheduled", &[("count", &changed)]));
+                    auto_sort(&mut tasks, &session, &config);
+                }
+            }
+
+            Some(MenuChoice::ViewByTag) => {
+                let counts = tag_counts(&tasks);
+                if counts.is_empty() {
+                    println!("\n{}", t("status.no_tags"));
+                    continue;
+                }
+
+                print_tag_legend(&counts);
+
+                println!("\n{}", t("prompt.select_tag"));
+                let Some(tag) = read_line()
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|num| counts.keys().nth(num.wrapping_sub(1)))
+                else {
+                    println!("\n{}", t("error.invalid_tag"));
+                    continue;
+                };
+
+                view_tasks_where(&tasks, &config, |task| task.tags.contains(tag));
+            }
+
+            Some(MenuChoice::CompletedToday) => {
+                let history = with_archive(&tasks, &config.archive_path);
+
+                let done_today = completed_on(&history, Local::now().date_naive());
+                println!(); // newline
+                if done_today.is_empty() {
+                    println!("\t{}", t("status.none_completed_today"));
+                }
+                for task in &done_today {
+                    println!("\t{}", task.name);
+                }
+            }
+
+            Some(MenuChoice::ReverseOrder) => {
+                session.reverse = !session.reverse;
+                if let Err(error) = write_session(&session, SESSION_PATH) {
+                    println!(
+                        "\n{}",
+                        t_fill("error.save_order", &[("error", &error.to_string())])
+                    );
+                }
+
+                if session.sort_key.is_none() {
+                    tasks.reverse();
+                }
+                sort_tasks(&mut tasks, &session, &config);
+                view_tasks(&tasks, &config);
+            }
+
+            Some(MenuChoice::ToggleBlocked) => {
+                session.show_blocked = !session.show_blocked;
+                if let Err(error) = write_session(&session, SESSION_PATH) {
+                    println!(
+                        "\n{}",
+                        t_fill("error.save_view", &[("error", &error.to_string())])
+                    );
+                }
+
+                if session.show_blocked {
+                    println!("\n{}", t("status.blocked_shown"));
+                } else {
+                    println!("\n{}", t("status.blocked_hidden"));
+                }
+            }
+
+            Some(MenuChoice::ExportCsv) => {
+                println!("\n{}", t("prompt.export_path"));
+                let csv_path = read_path();
+                let csv_path = if csv_path.is_empty() {
+                    "tasks.csv".to_string()
+                } else {
+                    csv_path
+                };
+
+                match export_csv(&tasks, &csv_path) {
+                    Ok(()) => {
+                        let key = if writes_disabled() {
+                            "status.not_written"
+                        } else {
+                            "status.exported"
+                        };
+                        let count = tasks.len().to_string();
+                        let values = [("count", count.as_str()), ("path", &csv_path)];
+                        println!("\n{}", t_fill(key, &values));
+                    }
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.export", &[("error", &error)]));
+                    }
+                }
+            }
+
+            Some(MenuChoice::ImportCsv) => {
+                println!("\n{}", t("prompt.import_path"));
+                let csv_path = read_path();
+                let csv_path = if csv_path.is_empty() {
+                    "tasks.csv".to_string()
+                } else {
+                    csv_path
+                };
+
+                match import_csv(&csv_path) {
+                    Ok(imported) => {
+                        let adding = imported.iter().filter(|task| !task.done).count();
+                        if !allow_more_tasks(&tasks, &config, adding) {
+                            continue;
+                        }
+                        let count = imported.len().to_string();
+                        for task in imported {
+                            add_task(&mut tasks, task);
+                        }
+                        let values = [("count", count.as_str()), ("path", &csv_path)];
+                        println!("\n{}", t_fill("status.imported", &values));
+                        auto_sort(&mut tasks, &session, &config);
+                    }
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.import", &[("error", &error)]));
+                    }
+                }
+            }
+
+            Some(MenuChoice::SplitTask) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_split"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                println!("\n{}", t("prompt.subtask_names"));
+                let names: Vec<String> = std::iter::from_fn(|| Some(read_line()))
+                    .take_while(|name| !name.is_empty())
+                    .collect();
+                if names.is_empty() {
+                    println!("\n{}", t("error.no_subtasks"));
+                    continue;
+                }
+                if !allow_more_tasks(&tasks, &config, names.len()) {
+                    continue;
+                }
+
+                let count = names.len().to_string();
+                split_task(&mut tasks, index, names);
+                let values = [("name", tasks[index].name.as_str()), ("count", &count)];
+                println!("\n{}", t_fill("status.split", &values));
+
+                let prompt = t_fill("prompt.keep_parent", &[("name", &tasks[index].name)]);
+                if !confirm(&prompt) {
+                    if let Err(error) = trash_task(&mut tasks, index, &config.trash_path) {
+                        println!(
+                            "\n{}",
+                            t_fill("error.trash_task", &[("error", &error.to_string())])
+                        );
+                    }
+                }
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::LogTime) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_log_time"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                println!("\n{}", t("prompt.minutes_spent"));
+                let minutes = match read_line().parse::<u32>() {
+                    Ok(minutes) => minutes,
+                    Err(_) => {
+                        let started = Instant::now();
+                        let name = tasks[index].name.as_str();
+                        println!("\n{}", t_fill("status.focusing", &[("name", name)]));
+                        read_line();
+                        // Rounded to the nearest minute.
+                        ((started.elapsed().as_secs() + 30) / 60) as u32
+                    }
+                };
+
+                log_time(&mut tasks[index], minutes);
+                let time = minutes_human(minutes);
+                let total = minutes_human(tasks[index].time_spent_minutes);
+                let values = [
+                    ("time", time.as_str()),
+                    ("name", &tasks[index].name),
+                    ("total", &total),
+                ];
+                println!("\n{}", t_fill("status.logged_time", &values));
+            }
+
+            Some(MenuChoice::EstimateReport) => {
+                let history = with_archive(&tasks, &config.archive_path);
+                print_estimate_report(&history);
+            }
+
+            Some(MenuChoice::PruneDependencies) => match prune_orphan_deps(&mut tasks) {
+                0 => println!("\n{}", t("status.no_missing_deps")),
+                pruned => {
+                    let count = pruned.to_string();
+                    println!("\n{}", t_fill("status.pruned_deps", &[("count", &count)]));
+                }
+            },
+
+            Some(MenuChoice::Review) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+                review_tasks(&mut tasks, &config);
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::SetPriority) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_priority"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                let current = tasks[index].priority.to_string();
+                let values = [("name", tasks[index].name.as_str()), ("current", &current)];
+                println!("\n{}", t_fill("prompt.priority_or_cycle", &values));
+                let input = read_line();
+                let priority = if input.is_empty() {
+                    tasks[index].priority.cycled()
+                } else if let Some(priority) = parse_priority(&input) {
+                    priority
+                } else {
+                    println!("\n{}", t("error.invalid_priority"));
+                    continue;
+                };
+
+                set_priority(&mut tasks, index, priority);
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::WeeklyReport) => {
+                println!("\n{}", t("prompt.report_path"));
+                let report_path = read_path();
+                let report_path = if report_path.is_empty() {
+                    "weekly_report.txt".to_string()
+                } else {
+                    report_path
+                };
+
+                let today = Local::now().date_naive();
+                let week_start =
+                    today - Duration::days(i64::from(today.weekday().num_days_from_monday()));
+                let history = with_archive(&tasks, &config.archive_path);
+                let report = weekly_report(&history, week_start);
+
+                let written = if writes_disabled() {
+                    Ok(())
+                } else {
+                    fs::write(&report_path, report)
+                };
+                match written {
+                    Ok(()) => {
+                        let key = if writes_disabled() {
+                            "status.not_written"
+                        } else {
+                            "status.report_written"
+                        };
+                        let values = [("path", report_path.as_str())];
+                        println!("\n{}", t_fill(key, &values));
+                    }
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.write_report", &[("error", &error)]));
+                    }
+                }
+            }
+
+            Some(MenuChoice::ShiftAll) => {
+                println!("\n{}", t("prompt.shift_days"));
+                let Ok(days) = read_line().parse::<i64>() else {
+                    println!("\n{}", t("error.offset_days"));
+                    continue;
+                };
+
+                let dated = tasks
+                    .iter()
+                    .filter(|task| !task.done && parse_due_date(task).is_some())
+                    .count();
+                if dated == 0 {
+                    println!("\n{}", t("status.no_dated_pending"));
+                    continue;
+                }
+
+                let (count, offset) = (dated.to_string(), days.to_string());
+                let values = [("count", count.as_str()), ("days", &offset)];
+                if confirm(&t_fill("prompt.confirm_shift", &values)) {
+                    let Some(changed) = shift_due_dates(&mut tasks, days) else {
+                        println!("\n{}", t_fill("error.shift_out_of_range", &values));
+                        continue;
+                    };
+                    let count = changed.to_string();
+                    let values = [("count", count.as_str()), ("days", &offset)];
+                    println!("\n{}", t_fill("status.shifted", &values));
+                    auto_sort(&mut tasks, &session, &config);
+                }
+            }
+
+            Some(MenuChoice::OpenLink) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks_where(&tasks, &config, |task| task.link.is_some());
+
+                println!("\n{}", t("prompt.select_open_link"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                match &tasks[index].link {
+                    Some(link) => match open::that(link) {
+                        Ok(()) => println!("\n{}", t_fill("status.opened_link", &[("link", link)])),
+                        Err(error) => {
+                            let values = [("link", link.as_str()), ("error", &error.to_string())];
+                            println!("\n{}", t_fill("error.open_link", &values));
+                        }
+                    },
+                    None => {
+                        let values = [("name", tasks[index].name.as_str())];
+                        println!("\n{}", t_fill("error.no_link", &values));
+                    }
+                }
+            }
+
+            Some(MenuChoice::MakeRepeatable) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_repeatable"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                println!("\n{}", t("prompt.repeat_how"));
+                match read_line().as_str() {
+                    "1" => {
+                        println!("\n{}", t("prompt.recurrence"));
+                        let Some(recurrence) = parse_recurrence(&read_line()) else {
+                            println!("\n{}", t("error.invalid_recurrence"));
+                            continue;
+                        };
+                        make_recurring(&mut tasks[index], recurrence);
+                        auto_sort(&mut tasks, &session, &config);
+                    }
+                    "2" => {
+                        let template = template_from_task(&tasks[index]);
+                        match save_template(&template, CONFIG_PATH) {
+                            Ok(()) => {
+                                let values =
+                                    [("name", template.name.as_str()), ("path", CONFIG_PATH)];
+                                println!("\n{}", t_fill("status.template_saved", &values));
+                                config.templates.push(template);
+                            }
+                            Err(error) => {
+                                let error = error.to_string();
+                                println!(
+                                    "\n{}",
+                                    t_fill("error.save_template", &[("error", &error)])
+                                );
+                            }
+                        }
+                    }
+                    _ => println!("\n{}", t("error.invalid_option")),
+                }
+            }
+
+            Some(MenuChoice::BulkEditDates) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                if let Err(error) = bulk_edit_due_dates(&mut tasks) {
+                    let error = error.to_string();
+                    println!(
+                        "\n{}",
+                        t_fill("error.bulk_edit_dates", &[("error", &error)])
+                    );
+                }
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::Forecast) => {
+                let today = Local::now().date_naive();
+                let projected = forecast(&tasks, config.daily_minutes, today);
+
+                println!(); // newline
+                if projected.is_empty() {
+                    println!("\t{}", t("status.no_estimates"));
+                }
+                for (index, date) in projected {
+                    let task = &tasks[index];
+                    let Some(date) = date else {
+                        let values = [("name", task.name.as_str())];
+                        println!("\t{}", t_fill("status.forecast_beyond", &values));
+                        continue;
+                    };
+                    let late = parse_due_date(task).is_some_and(|due| date > due);
+                    let date = date.format(&config.date_format).to_string();
+                    let key = if late {
+                        "status.forecast_late"
+                    } else {
+                        "status.forecast"
+                    };
+                    let values = [("date", date.as_str()), ("name", &task.name)];
+                    println!("\t{}", t_fill(key, &values));
+                }
+            }
+
+            Some(MenuChoice::SetStatus) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_status"));
+                let Some(index) = read_index_input(&tasks) else {
+                    continue;
+                };
+
+                let current = tasks[index].status().to_string();
+                let values = [("name", tasks[index].name.as_str()), ("current", &current)];
+                println!("\n{}", t_fill("prompt.status", &values));
+                match parse_status(&read_line()) {
+                    Some(status) => {
+                        set_status(&mut tasks, index, status, config.recurrence_from);
+                        auto_sort(&mut tasks, &session, &config);
+                    }
+                    None => println!("\n{}", t("error.invalid_status")),
+                }
+            }
+
+            Some(MenuChoice::SurpriseMe) => {
+                match random_task(&tasks, config.random_by_priority, &mut rand::thread_rng()) {
+                    Some(task) => {
+                        println!("\n{}", t("status.surprise"));
+                        view_task_details(task);
+                    }
+                    None => println!("\n{}", t("status.all_done")),
+                }
+            }
+
+            Some(MenuChoice::Calendar) => {
+                println!("\n{}", t("prompt.month"));
+                let input = read_line();
+                let month = if input.is_empty() {
+                    Some(Local::now().date_naive())
+                } else {
+                    NaiveDate::parse_from_str(&format!("{input}-01"), DATE_FORMAT).ok()
+                };
+
+                match month {
+                    Some(month) => {
+                        print!("\n{}", render_calendar(&tasks, month.year(), month.month()))
+                    }
+                    None => println!("\n{}", t("error.invalid_month")),
+                }
+            }
+
+            Some(MenuChoice::ResetAll) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                let count = tasks.len().to_string();
+                if confirm(&t_fill("prompt.confirm_reset", &[("count", &count)])) {
+                    let reset = reset_all(&mut tasks).to_string();
+                    println!("\n{}", t_fill("status.reset", &[("count", &reset)]));
+                    auto_sort(&mut tasks, &session, &config);
+                }
+            }
+
+            Some(MenuChoice::SwitchFile) => {
+                println!("\n{}", t_fill("prompt.switch_path", &[("path", &path)]));
+                let new_path = read_path();
+                if new_path.is_empty() || new_path == path {
+                    continue;
+                }
+
+                let new_lock = if writes_disabled() {
+                    None
+                } else {
+                    match lock_tasks_file(&new_path) {
+                        Ok(new_lock) => Some(new_lock),
+                        Err(error) => {
+                            println!("\n{error}");
+                            continue;
+                        }
+                    }
+                };
+                let mut new_tasks = match read_tasks_or_empty(&new_path) {
+                    Ok(new_tasks) => new_tasks,
+                    Err(error) => {
+                        let values = [
+                            ("new_path", new_path.as_str()),
+                            ("path", &path),
+                            ("error", &error.to_string()),
+                        ];
+                        println!("\n{}", t_fill("error.switch_read", &values));
+                        continue;
+                    }
+                };
+
+                // The current list is saved before switching, so nothing is lost.
+                if let Err(error) =
+                    rotate_backups(&path, config.backups).and_then(|()| save_tasks(&tasks, &path))
+                {
+                    let values = [("path", path.as_str()), ("error", &error.to_string())];
+                    println!("\n{}", t_fill("error.switch_save", &values));
+                    continue;
+                }
+                println!("\n{}", t_fill("status.saved", &[("path", &path)]));
+
+                assign_missing_ids(&mut new_tasks);
+                dedupe_ids(&mut new_tasks);
+                sort_tasks(&mut new_tasks, &session, &config);
+                tasks = new_tasks;
+                path = new_path;
+                _lock = new_lock;
+                let count = tasks.len().to_string();
+                let values = [("path", path.as_str()), ("count", &count)];
+                println!("{}", t_fill("status.switched", &values));
+            }
+
+            Some(MenuChoice::GroupByTag) => print_tag_groups(&tasks, &config),
+
+            Some(MenuChoice::AddNote) => {
+                println!("\n{}", t("prompt.note"));
+                let text = read_line();
+                if text.is_empty() {
+                    continue;
+                }
+
+                match append_note(&text, NOTES_PATH) {
+                    Ok(()) => {
+                        println!("\n{}", t_fill("status.note_saved", &[("path", NOTES_PATH)]))
+                    }
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.save_note", &[("error", &error)]));
+                    }
+                }
+            }
+
+            Some(MenuChoice::ViewNotes) => match read_notes(NOTES_PATH) {
+                Ok(notes) if notes.is_empty() => println!("\n{}", t("status.no_notes")),
+                Ok(notes) => {
+                    println!();
+                    for note in notes {
+                        println!(
+                            "\t{}  {}",
+                            note.created_at.format("%Y-%m-%d %H:%M"),
+                            note.text
+                        );
+                    }
+                }
+                Err(error) => {
+                    let values = [("path", NOTES_PATH), ("error", &error.to_string())];
+                    println!("\n{}", t_fill("error.read_notes", &values));
+                }
+            },
+
+            Some(MenuChoice::ScheduleUndated) => {
+                schedule_undated(&mut tasks, &config);
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::ToggleFocus) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                view_tasks(&tasks, &config);
+
+                println!("\n{}", t("prompt.select_focus"));
+
+                if let Some(index) = read_index_input(&tasks) {
+                    let task = &tasks[index];
+                    if session.focus.remove(&task.id) {
+                        println!("\n{}", t_fill("status.unfocused", &[("name", &task.name)]));
+                    } else {
+                        session.focus.insert(task.id);
+                        println!("\n{}", t_fill("status.focused", &[("name", &task.name)]));
+                    }
+                    if let Err(error) = write_session(&session, SESSION_PATH) {
+                        println!(
+                            "\n{}",
+                            t_fill("error.save_focus", &[("error", &error.to_string())])
+                        );
+                    }
+                }
+            }
+
+            Some(MenuChoice::ViewFocus) => {
+                // Tasks deleted or archived since they were picked drop out of the list.
+                let before = session.focus.len();
+                session
+                    .focus
+                    .retain(|id| tasks.iter().any(|task| task.id == *id));
+                if session.focus.len() != before {
+                    if let Err(error) = write_session(&session, SESSION_PATH) {
+                        println!(
+                            "\n{}",
+                            t_fill("error.save_focus", &[("error", &error.to_string())])
+                        );
+                    }
+                }
+
+                if session.focus.is_empty() {
+                    println!("\n{}", t("status.focus_empty"));
+                    continue;
+                }
+                view_tasks_where(&tasks, &config, |task| session.focus.contains(&task.id));
+            }
+
+            Some(MenuChoice::MergeDuplicates) => {
+                merge_duplicates(&mut tasks, &config);
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::Snapshot) => {
+                // Snapshots sit beside the tasks file they were taken from.
+                let dir = Path::new(&path)
+                    .parent()
+                    .map(|dir| dir.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                match snapshot(&tasks, &dir) {
+                    Ok(written) => {
+                        let key = if writes_disabled() {
+                            "status.not_written"
+                        } else {
+                            "status.snapshot_saved"
+                        };
+                        let written = written.display().to_string();
+                        println!("\n{}", t_fill(key, &[("path", &written)]));
+                    }
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.save_snapshot", &[("error", &error)]));
+                    }
+                }
+            }
+
+            Some(MenuChoice::BulkPriority) => {
+                let filter = read_filter(&config);
+                let matching = tasks.iter().filter(|task| filter.matches(task)).count();
+                if matching == 0 {
+                    println!("\n{}", t("status.no_matches"));
+                    continue;
+                }
+
+                let count = matching.to_string();
+                println!("\n{}", t_fill("prompt.bulk_priority", &[("count", &count)]));
+                let Some(priority) = parse_priority(&read_line()) else {
+                    println!("\n{}", t("error.invalid_priority"));
+                    continue;
+                };
+
+                let changed = set_priority_where(&mut tasks, &filter, priority).to_string();
+                println!(
+                    "\n{}",
+                    t_fill("status.bulk_priority", &[("count", &changed)])
+                );
+                auto_sort(&mut tasks, &session, &config);
+            }
+
+            Some(MenuChoice::Reorder) => {
+                if no_tasks(&tasks) {
+                    continue;
+                }
+
+                // A sort would undo the new order, so the list goes back to manual order.
+                if reorder_tasks(&mut tasks, &config) && session.sort_key.is_some() {
+                    session.sort_key = None;
+                    session.reverse = false;
+                    println!("{}", t("status.sorting_off"));
+                    if let Err(error) = write_session(&session, SESSION_PATH) {
+                        println!(
+                            "\n{}",
+                            t_fill("error.save_order", &[("error", &error.to_string())])
+                        );
+                    }
+                }
+            }
+
+            // Save task vector to the tasks file and exit program.
+            Some(MenuChoice::Quit) if writes_disabled() => {
+                println!("\n{}", t("status.ephemeral_discarded"));
+                break;
+            }
+
+            Some(MenuChoice::Quit) => {
+                // Completed tasks are only archived along with a save, so discarding changes
+                // leaves the archive untouched too.
+                if should_save_on_exit(config.on_exit, &path) {
+                    println!("\n{}", t("status.archiving"));
+                    match archive_complete_tasks(&tasks, &config.archive_path) {
+                        Ok(()) => {
+                            remove_complete_tasks(&mut tasks);
+                            let values = [("path", config.archive_path.as_str())];
+                            println!("{}", t_fill("status.archived", &values));
+                        }
+                        Err(error) => {
+                            let error = error.to_string();
+                            println!("{}", t_fill("error.archive", &[("error", &error)]));
+                        }
+                    }
+
+                    println!("\n{}", t("status.saving"));
+                    loop {
+                        match rotate_backups(&path, config.backups)
+                            .and_then(|()| save_tasks(&tasks, &path))
+                        {
+                            Ok(()) => {
+                                println!("{}", t_fill("status.saved", &[("path", &path)]));
+                                break;
+                            }
+                            Err(error) => {
+                                let values =
+                                    [("path", path.as_str()), ("error", &error.to_string())];
+                                println!("\n{}", t_fill("error.save_tasks", &values));
+                                println!("{}", t("prompt.save_path_or_discard"));
+                                // The lock follows the save to a new path, and is kept
+                                // as it is when the same path is retried.
+                                path = loop {
+                                    let new_path = read_path();
+                                    if new_path.is_empty() || new_path == path {
+                                        break new_path;
+                                    }
+                                    match lock_tasks_file(&new_path) {
+                                        Ok(lock) => {
+                                            _lock = Some(lock);
+                                            break new_path;
+                                        }
+                                        Err(error) => {
+                                            println!("\n{error}");
+                                            println!("{}", t("prompt.save_path_or_discard"));
+                                        }
+                                    }
+                                };
+                                if path.is_empty() {
+                                    println!("{}", t("status.discarded"));
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                } else {
+                    println!("\n{}", t("status.discarded"));
+                }
+
+                // The view is restored next time whether or not the tasks were saved.
+                if let Err(error) = write_session(&session, SESSION_PATH) {
+                    let error = error.to_string();
+                    eprintln!("{}", t_fill("error.save_session", &[("error", &error)]));
+                }
+                if let Some(editor) = editor.as_mut() {
+                    if let Err(error) = editor.save_history(HISTORY_PATH) {
+                        let error = error.to_string();
+                        eprintln!("{}", t_fill("error.save_history", &[("error", &error)]));
+                    }
+                }
+
+                println!("\n{}", t("status.exiting"));
+                break;
+            }
+
+            None => println!("\n{}", t("error.invalid_option")),
+        }
+    }
+
+    Ok(())
+}
+
+// Marks the task with the given ID as complete and saves the list, for scripts running
+// `todolist done <id>`. Fails if the ID is missing or invalid, or no task has it.
+fn complete_by_id(id: Option<&str>) -> Result<()> {
+    let config = load_config(false);
+    init_translations(&config.locale);
+    let id = id
+        .and_then(|id| id.parse::<u32>().ok())
+        .ok_or_else(|| TodoError::InvalidInput(t("error.done_usage").to_string()))?;
+
+    init_audit_log(&config);
+    let _lock = if writes_disabled() {
+        None
+    } else {
+        Some(lock_tasks_file(&config.data_path)?)
+    };
+
+    let mut tasks = read_tasks_for_script(&config)?;
+    let index = tasks
+        .iter()
+        .position(|task| task.id == id)
+        .ok_or(TodoError::NoTaskWithId(id))?;
+    complete_task(&mut tasks, index, config.recurrence_from);
+
+    rotate_backups(&config.data_path, config.backups)?;
+    save_tasks(&tasks, &config.data_path)
+}
+
+// Reads the tasks for a command-line flag or subcommand, giving IDs to any tasks without
+// one just as the interactive menu does, so the IDs shown match the ones `done` accepts.
+fn read_tasks_for_script(config: &Config) -> Result<Vec<Task>> {
+    let mut tasks = read_tasks_or_empty(&config.data_path)?;
+    reserve_retired_ids(config);
+    assign_missing_ids(&mut tasks);
+    dedupe_ids(&mut tasks);
+    Ok(tasks)
+}
+
+// Locks the tasks file at `path`, explaining how to recover if another instance holds it.
+fn lock_tasks_file(path: &str) -> Result<FileLock> {
+    FileLock::acquire(path).map_err(|source| {
+        if source.kind() == io::ErrorKind::AlreadyExists {
+            TodoError::Locked(path.to_string())
+        } else {
+            TodoError::LockFile {
+                path: path.to_string(),
+                source,
+            }
+        }
+    })
+}
+
+// Starts recording changes to the audit log, if it is enabled.
+fn init_audit_log(config: &Config) {
+    if config.audit_log {
+        let _ = AUDIT_LOG.set(AuditLog {
+            path: config.audit_log_path.clone(),
+            max_entries: config.audit_log_max_entries,
+        });
+    }
+}
+
+// Decides whether to save when quitting, asking first if `on_exit` is set to prompt.
+fn should_save_on_exit(on_exit: OnExit, path: &str) -> bool {
+    match on_exit {
+        OnExit::SaveSilently => true,
+        OnExit::Prompt => confirm(&t_fill("prompt.confirm_save", &[("path", path)])),
+        OnExit::NeverSave => false,
+    }
+}
+
+// Returns the archived tasks followed by the current ones, for reports covering both.
+fn with_archive(tasks: &[Task], archive_path: &str) -> Vec<Task> {
+    let mut history = read_tasks_or_empty(archive_path).unwrap_or_else(|error| {
+        let error = error.to_string();
+        println!("\n{}", t_fill("error.read_archive", &[("error", &error)]));
+        vec![]
+    });
+    history.extend(tasks.iter().cloned());
+    history
+}
+
+// Appends all completed tasks to the archive file at `archive_path`.
+fn archive_complete_tasks(tasks: &[Task], archive_path: &str) -> Result<()> {
+    if !tasks.iter().any(|task| task.done) {
+        return Ok(());
+    }
+
+    let mut archive = read_tasks_or_empty(archive_path)?;
+    archive.extend(tasks.iter().filter(|task| task.done).cloned());
+    save_tasks(&archive, archive_path)
+}
+
+// Removes all completed tasks from tasks vector.
+fn remove_complete_tasks(tasks: &mut Vec<Task>) {
+    tasks.retain(|task| !task.done);
+}
+
+// Reads tasks from the file at `path` and returns them as a vector.
+// Accepts either a bare array of tasks or an object wrapping it in a `tasks` key, or one
+// task per line if the path ends in `.jsonl`.
+fn read_tasks(path: &str) -> Result<Vec<Task>> {
+    if is_jsonl(path) {
+        return read_tasks_jsonl(path);
+    }
+
+    let file = File::open(path)?;
+    let mut contents = String::new();
+    BufReader::new(file).read_to_string(&mut contents)?;
+
+    match serde_json::from_str::<Vec<Task>>(&contents) {
+        Ok(tasks) => Ok(tasks),
+        Err(error) => match serde_json::from_str::<TaskFile>(&contents) {
+            Ok(file) => Ok(file.tasks),
+            Err(_) => Err(error.into()),
+        },
+    }
+}
+
+// Checks whether tasks at `path` are stored as JSON Lines, one task per line.
+fn is_jsonl(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|extension| extension == "jsonl")
+}
+
+// Reads tasks from a JSON Lines file, where each non-blank line is one task.
+fn read_tasks_jsonl(path: &str) -> Result<Vec<Task>> {
+    let contents = fs::read_to_string(path)?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|error| TodoError::Parse {
+                line: i + 1,
+                message: error.to_string(),
+            })
+        })
+        .collect()
+}
+
+// Writes the tasks to a JSON Lines file, one task per line, replacing its contents.
+fn save_tasks_jsonl(tasks: &[Task], path: &str) -> Result<()> {
+    let mut contents = String::new();
+    for task in tasks {
+        contents.push_str(&serde_json::to_string(task)?);
+        contents.push('\n');
+    }
+    Ok(fs::write(path, contents)?)
+}
+
+// Lists fields in the task file that don't belong to a task, e.g. misspelled keys.
+// Found by comparing each task's keys with the keys it has once parsed and re-serialized.
+fn find_unknown_fields(path: &str) -> Result<Vec<String>> {
+    let contents = fs::read_to_string(path)?;
+    let entries = if is_jsonl(path) {
+        let lines = contents.lines().filter(|line| !line.trim().is_empty());
+        serde_json::Value::Array(
+            lines
+                .map(serde_json::from_str)
+                .collect::<serde_json::Result<_>>()?,
+        )
+    } else {
+        let value: serde_json::Value = serde_json::from_str(&contents)?;
+        match &value {
+            serde_json::Value::Object(file) => file.get("tasks").cloned().unwrap_or_default(),
+            _ => value,
+        }
+    };
+
+    let mut unknown = vec![];
+    for (i, entry) in entries.as_array().into_iter().flatten().enumerate() {
+        let (Some(fields), Ok(task)) = (entry.as_object(), Task::deserialize(entry)) else {
+            continue;
+        };
+        let known = serde_json::to_value(task)?;
+
+        for key in fields.keys() {
+            if known.get(key).is_none() {
+                let num = (i + 1).to_string();
+                unknown.push(t_fill(
+                    "error.unknown_field",
+                    &[("num", &num), ("field", key)],
+                ));
+            }
+        }
+    }
+
+    Ok(unknown)
+}
+
+// Columns written by `export_csv` and expected by `import_csv`, in order.
+const CSV_HEADER: [&str; 4] = ["name", "desc", "due_date", "done"];
+
+// Writes the tasks to a CSV file with a header row, quoting fields where needed.
+fn export_csv(tasks: &[Task], path: &str) -> Result<()> {
+    if writes_disabled() {
+        return Ok(());
+    }
+
+    let mut contents = CSV_HEADER.join(",");
+    contents.push('\n');
+    for task in tasks {
+        let fields = [
+            csv_field(&task.name),
+            csv_field(&task.desc),
+            csv_field(&task.due_date),
+            task.done.to_string(),
+        ];
+        contents.push_str(&fields.join(","));
+        contents.push('\n');
+    }
+
+    Ok(fs::write(path, contents)?)
+}
+
+// Quotes a CSV field if it contains a comma, quote, or line break, doubling any quotes.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Reads tasks from a CSV file in the format written by `export_csv`. A header row is
+// skipped if present. Rows that can't be parsed are reported and skipped, so one bad
+// row doesn't stop the rest from importing.
+fn import_csv(path: &str) -> Result<Vec<Task>> {
+    let contents = normalize_line_endings(&fs::read_to_string(path)?);
+    let mut tasks = vec![];
+
+    for (i, record) in parse_csv(&contents).into_iter().enumerate() {
+        let row = i + 1;
+        if row == 1 && record.iter().map(String::as_str).eq(CSV_HEADER) {
+            continue;
+        }
+        if record.iter().all(|field| field.is_empty()) {
+            continue;
+        }
+
+        let [name, desc, due_date, done] = match <[String; 4]>::try_from(record) {
+            Ok(fields) => fields,
+            Err(record) => {
+                let (row, found) = (row.to_string(), record.len().to_string());
+                let values = [("row", row.as_str()), ("found", &found)];
+                println!("\t{}", t_fill("error.csv_columns", &values));
+                continue;
+            }
+        };
+        let Ok(done) = done.trim().to_lowercase().parse::<bool>() else {
+            let row = row.to_string();
+            let values = [("row", row.as_str()), ("found", &done)];
+            println!("\t{}", t_fill("error.csv_done", &values));
+            continue;
+        };
+        if !due_date.is_empty() && NaiveDate::parse_from_str(&due_date, DATE_FORMAT).is_err() {
+            let row = row.to_string();
+            let values = [
+                ("row", row.as_str()),
+                ("format", DATE_FORMAT),
+                ("found", &due_date),
+            ];
+            println!("\t{}", t_fill("error.csv_due_date", &values));
+            continue;
+        }
+
+        tasks.push(Task {
+            name,
+            desc,
+            due_date,
+            done,
+            ..Task::default()
+        });
+    }
+
+    Ok(tasks)
+}
+
+// Converts Windows (`\r\n`) and old Mac (`\r`) line endings to `\n`, so files saved on
+// any system import without stray carriage returns in names or dates.
+fn normalize_line_endings(contents: &str) -> String {
+    contents.replace("\r\n", "\n").replace('\r', "\n")
+}
+
+// Splits CSV text into records of fields. Quoted fields may contain commas, line breaks,
+// and doubled quotes.
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if field.is_empty() => in_quotes = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+// Reads tasks like `read_tasks`, but treats a missing or blank file as an empty list.
+fn read_tasks_or_empty(path: &str) -> Result<Vec<Task>> {
+    match read_tasks(path) {
+        Err(error) if error.is_io(io::ErrorKind::NotFound) => Ok(vec![]),
+        Err(TodoError::Json(error)) if error.is_eof() && is_blank_file(path) => Ok(vec![]),
+        result => result,
+    }
+}
+
+// Checks whether the file at `path` holds nothing but whitespace, as a new tasks file does.
+fn is_blank_file(path: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|contents| contents.trim().is_empty())
+}
+
+// Serializes the tasks and writes them to the file at `path`, replacing its contents.
+// Paths ending in `.jsonl` are written one task per line.
+fn save_tasks(tasks: &[Task], path: &str) -> Result<()> {
+    if writes_disabled() {
+        return Ok(());
+    }
+    if is_jsonl(path) {
+        return save_tasks_jsonl(tasks, path);
+    }
+
+    let serialized_tasks = serde_json::to_string(tasks)?;
+    let mut file = File::create(path)?;
+    file.write_all(serialized_tasks.as_bytes())?;
+    Ok(())
+}
+
+// Copies the tasks to a file in `dir` named after today's date, such as
+// `tasks-2024-06-03.json`, returning its path. A second snapshot on the same day gets the
+// time added to its name rather than replacing the first.
+fn snapshot(tasks: &[Task], dir: &str) -> Result<PathBuf> {
+    let now = Local::now();
+    let mut path = Path::new(dir).join(format!("tasks-{}.json", now.format("%Y-%m-%d")));
+    if path.exists() {
+        path = Path::new(dir).join(format!("tasks-{}.json", now.format("%Y-%m-%d-%H%M%S")));
+    }
+
+    save_tasks(tasks, &path.to_string_lossy())?;
+    Ok(path)
+}
+
+// Reads the scratchpad notes from the file at `path`, or none if it doesn't exist yet.
+fn read_notes(path: &str) -> Result<Vec<Note>> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(serde_json::from_str(&contents)?),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(error) => Err(error.into()),
+    }
+}
+
+// Adds a note stamped with the current time to the end of the notes file at `path`.
+fn append_note(text: &str, path: &str) -> Result<()> {
+    if writes_disabled() {
+        return Ok(());
+    }
+
+    let mut notes = read_notes(path)?;
+    notes.push(Note {
+        created_at: Local::now(),
+        text: text.to_string(),
+    });
+    Ok(fs::write(path, serde_json::to_string(&notes)?)?)
+}
+
+// Reads settings from `CONFIG_PATH`, walking the user through first-run setup if it doesn't
+// exist. Setup is skipped when `interactive` is false, so flags like `--json` never prompt.
+fn load_config(interactive: bool) -> Config {
+    let mut config = if Path::new(CONFIG_PATH).exists() {
+        read_config(CONFIG_PATH).unwrap_or_else(|error| {
+            let values = [("path", CONFIG_PATH), ("error", &error.to_string())];
+            eprintln!("{}", t_fill("error.read_config", &values));
+            Config::default()
+        })
+    } else if interactive {
+        first_run_setup()
+    } else {
+        Config::default()
+    };
+
+    apply_env_overrides(&mut config);
+    for path in [
+        &mut config.data_path,
+        &mut config.trash_path,
+        &mut config.archive_path,
+        &mut config.audit_log_path,
+    ] {
+        *path = expand_path(path).display().to_string();
+    }
+    config
+}
+
+// Expands a leading `~` to the home directory, and `$NAME` or `${NAME}` to the value of
+// that environment variable, so configured paths like `~/tasks.json` or
+// `$HOME/tasks.json` don't create a literal `~` or `$HOME` folder. Variables that aren't
+// set are left as written.
+fn expand_path(p: &str) -> PathBuf {
+    let home = env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok();
+    let mut expanded = String::new();
+    let mut rest = p;
+    if let (Some(after), Some(home)) = (p.strip_prefix('~'), &home) {
+        if after.is_empty() || after.starts_with(['/', '\\']) {
+            expanded.push_str(home);
+            rest = after;
+        }
+    }
+
+    while let Some(dollar) = rest.find('$') {
+        expanded.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        let (name, len) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(close) => (&braced[..close], close + 2),
+                None => ("", 0),
+            },
+            None => {
+                let end = after
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            }
+        };
+
+        match env::var(name) {
+            Ok(value) if !name.is_empty() => {
+                expanded.push_str(&value);
+                rest = &after[len..];
+            }
+            _ => {
+                expanded.push('$');
+                rest = after;
+            }
+        }
+    }
+
+    expanded.push_str(rest);
+    PathBuf::from(expanded)
+}
+
+// Loads the translations for `locale` from `LOCALE_DIR`, warning and falling back to
+// English if the file can't be read. English itself needs no file.
+fn init_translations(locale: &str) {
+    if locale == "en" {
+        return;
+    }
+
+    let path = format!("{LOCALE_DIR}/{locale}.toml");
+    match read_translations(&path) {
+        Ok(translations) => {
+            let _ = TRANSLATIONS.set(translations);
+        }
+        Err(error) => {
+            let values = [("path", path.as_str()), ("error", &error.to_string())];
+            eprintln!("{}", t_fill("error.read_translations", &values));
+        }
+    }
+}
+
+// Reads a translation file of `key = "text"` pairs.
+fn read_translations(path: &str) -> Result<BTreeMap<String, String>> {
+    let contents = fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+// Looks up the user-facing text for `key` in the loaded translations, then in English.
+// Unknown keys are returned as-is so a missing entry is visible rather than blank.
+fn t(key: &str) -> &str {
+    TRANSLATIONS
+        .get()
+        .and_then(|translations| translations.get(key))
+        .map(String::as_str)
+        .or_else(|| {
+            ENGLISH
+                .iter()
+                .find(|(english_key, _)| *english_key == key)
+                .map(|(_, text)| *text)
+        })
+        .unwrap_or(key)
+}
+
+// Looks up `key` like `t`, filling each `{placeholder}` in its text with a value.
+fn t_fill(key: &str, values: &[(&str, &str)]) -> String {
+    render_template(t(key), values)
+}
+
+// Reads settings from the TOML file at `path`.
+fn read_config(path: &str) -> Result<Config> {
+    let contents = fs::read_to_string(path)?;
+    Ok(toml::from_str(&contents)?)
+}
+
+// Writes settings to the TOML file at `path`, replacing its contents.
+fn save_config(config: &Config, path: &str) -> Result<()> {
+    if writes_disabled() {
+        return Ok(());
+    }
+
+    let contents = toml::to_string(config)?;
+    Ok(fs::write(path, contents)?)
+}
+
+// Lets environment variables override settings from the config file.
+fn apply_env_overrides(config: &mut Config) {
+    if let Some(width) = env::var("TODO_NAME_WIDTH")
+        .ok()
+        .and_then(|width| width.parse::<usize>().ok())
+    {
+        config.name_width = width;
+    }
+}
+
+// Asks a new user for their preferred settings, then saves them to `CONFIG_PATH`.
+fn first_run_setup() -> Config {
+    let mut config = Config::default();
+    println!("\n{}", t("prompt.setup_welcome"));
+
+    let values = [("default", config.data_path.as_str())];
+    println!("\n{}", t_fill("prompt.setup_data_path", &values));
+    let data_path = read_line();
+    if !data_path.is_empty() {
+        config.data_path = data_path;
+    }
+
+    loop {
+        let values = [("default", config.date_format.as_str())];
+        println!("\n{}", t_fill("prompt.setup_date_format", &values));
+        let date_format = read_line();
+        if date_format.is_empty() {
+            break;
+        }
+        if is_valid_date_format(&date_format) {
+            config.date_format = date_format;
+            break;
+        }
+        let values = [("format", date_format.as_str())];
+        println!("\n{}", t_fill("error.invalid_date_format", &values));
+    }
+
+    println!("\n{}", t("prompt.setup_confirm_deletes"));
+    config.confirm_deletes = !read_line().eq_ignore_ascii_case("n");
+
+    match save_config(&config, CONFIG_PATH) {
+        Ok(()) => println!(
+            "\n{}",
+            t_fill("status.setup_saved", &[("path", CONFIG_PATH)])
+        ),
+        Err(error) => {
+            let values = [("path", CONFIG_PATH), ("error", &error.to_string())];
+            eprintln!("{}", t_fill("error.save_config", &values));
+        }
+    }
+
+    config
+}
+
+// Checks that a chrono format string can round-trip a full date.
+fn is_valid_date_format(format: &str) -> bool {
+    let today = Local::now().date_naive();
+    let mut formatted = String::new();
+    if write!(formatted, "{}", today.format(format)).is_err() {
+        return false;
+    }
+
+    NaiveDate::parse_from_str(&formatted, format) == Ok(today)
+}
+
+// Reads saved view preferences, falling back to defaults if the file is missing or invalid.
+fn read_session(path: &str) -> SessionState {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// Saves view preferences to `path`, replacing its contents.
+fn write_session(session: &SessionState, path: &str) -> Result<()> {
+    if writes_disabled() {
+        return Ok(());
+    }
+
+    Ok(fs::write(path, serde_json::to_string(session)?)?)
+}
+
+// Keeps up to `keep` previous versions of the file at `path` before it is overwritten:
+// `path.1` becomes `path.2` and so on, the oldest is dropped, and `path` is copied to `path.1`.
+fn rotate_backups(path: &str, keep: usize) -> Result<()> {
+    if writes_disabled() || keep == 0 || !Path::new(path).exists() {
+        return Ok(());
+    }
+
+    for i in (1..keep).rev() {
+        let older = format!("{path}.{i}");
+        if Path::new(&older).exists() {
+            fs::rename(&older, format!("{path}.{}", i + 1))?;
+        }
+    }
+
+    fs::copy(path, format!("{path}.1"))?;
+    Ok(())
+}
+
+// Checks whether `--ephemeral` was given, in which case every write path is skipped.
+fn writes_disabled() -> bool {
+    EPHEMERAL.load(AtomicOrdering::Relaxed)
+}
+
+// Appends a timestamped line describing a change to the audit log, if it is enabled.
+// Once the log holds more than its maximum number of entries, the oldest are dropped.
+// Failures are reported but never interrupt the change being logged.
+fn log_action(action: &str) {
+    let Some(log) = AUDIT_LOG.get().filter(|_| !writes_disabled()) else {
+        return;
+    };
+
+    let line = format!("{}\t{}\n", Local::now().format("%Y-%m-%d %H:%M:%S"), action);
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log.path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .and_then(|()| fs::read_to_string(&log.path))
+        .and_then(|contents| match cap_entries(&contents, log.max_entries) {
+            Some(capped) => fs::write(&log.path, capped),
+            None => Ok(()),
+        });
+    if let Err(error) = result {
+        let values = [("path", log.path.as_str()), ("error", &error.to_string())];
+        eprintln!("{}", t_fill("error.write_audit_log", &values));
+    }
+}
+
+// Keeps only the newest `max` lines of a log, or returns `None` if it is within the cap.
+fn cap_entries(contents: &str, max: usize) -> Option<String> {
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines.len() <= max {
+        return None;
+    }
+
+    let mut capped = lines[lines.len() - max..].join("\n");
+    if !capped.is_empty() {
+        capped.push('\n');
+    }
+    Some(capped)
+}
+
+// Checks that the file at `path` can be opened for writing, creating it if needed.
+fn check_writable(path: &str) -> Result<()> {
+    OpenOptions::new().create(true).append(true).open(path)?;
+    Ok(())
+}
+
+// Prompts for an alternate path until one is writable, or the user chooses to continue anyway.
+fn ensure_writable(mut path: String) -> String {
+    while let Err(error) = check_writable(&path) {
+        let values = [("path", path.as_str()), ("error", &error.to_string())];
+        println!("\n{}", t_fill("error.not_writable", &values));
+        println!("{}", t("prompt.save_path_or_continue"));
+        let new_path = read_path();
+        if new_path.is_empty() {
+            break;
+        }
+        path = new_path;
+    }
+
+    path
+}
+
+// Adds a new task to the vector of tasks, giving it the next unused ID and a unique
+// creation time.
+fn add_task(tasks: &mut Vec<Task>, mut new_task: Task) {
+    new_task.id = next_id(tasks);
+    new_task.created_at = Some(unique_timestamp(tasks));
+    log_action(&format!("added '{}'", new_task.name));
+    tasks.push(new_task);
+}
+
+// Lowercased words of a task name with punctuation removed, so "Email Bob" and
+// "email bob re: report" share the words "email" and "bob".
+fn name_words(name: &str) -> BTreeSet<String> {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+// Scores how alike two names are from 0 to 1, by the share of words they have in common.
+fn name_similarity(a: &str, b: &str) -> f64 {
+    let (a, b) = (name_words(a), name_words(b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    let shared = a.intersection(&b).count();
+    2.0 * shared as f64 / (a.len() + b.len()) as f64
+}
+
+// Groups the indices of tasks whose names are at least `threshold` similar to the first
+// task in the group. Each task belongs to at most one group, and lone tasks are left out.
+fn find_similar(tasks: &[Task], threshold: f64) -> Vec<Vec<usize>> {
+    let mut grouped = vec![false; tasks.len()];
+    let mut groups = vec![];
+
+    for i in 0..tasks.len() {
+        if grouped[i] {
+            continue;
+        }
+        let group: Vec<usize> = (i..tasks.len())
+            .filter(|&j| {
+                !grouped[j]
+                    && (j == i || name_similarity(&tasks[i].name, &tasks[j].name) >= threshold)
+            })
+            .collect();
+        if group.len() > 1 {
+            for &j in &group {
+                grouped[j] = true;
+            }
+            groups.push(group);
+        }
+    }
+    groups
+}
+
+// Folds the tasks at `others` into the one at `keep`, combining their tags, notes and
+// dependencies and keeping the earliest creation time. Tasks that depended on a merged
+// task depend on the kept one instead. The merged tasks are left for the caller to remove.
+fn merge_tasks(tasks: &mut [Task], keep: usize, others: &[usize]) {
+    let kept_id = tasks[keep].id;
+    let merged_ids: Vec<u32> = others.iter().map(|&i| tasks[i].id).collect();
+
+    for &i in others {
+        let other = tasks[i].clone();
+        let task = &mut tasks[keep];
+        for tag in other.tags {
+            if !task.tags.contains(&tag) {
+                task.tags.push(tag);
+            }
+        }
+        if !other.notes.is_empty() {
+            if !task.notes.is_empty() {
+                task.notes.push('\n');
+            }
+            task.notes.push_str(&other.notes);
+        }
+        for id in other.blocked_by {
+            if id != kept_id && !task.blocked_by.contains(&id) {
+                task.blocked_by.push(id);
+            }
+        }
+        task.created_at = match (task.created_at, other.created_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+
+    for task in tasks.iter_mut() {
+        let mut depended = false;
+        task.blocked_by.retain(|id| {
+            let merged = merged_ids.contains(id);
+            depended |= merged;
+            !merged
+        });
+        if depended && task.id != kept_id && !task.blocked_by.contains(&kept_id) {
+            task.blocked_by.push(kept_id);
+        }
+    }
+
+    log_action(&format!(
+        "merged {} task(s) into '{}'",
+        others.len(),
+        tasks[keep].name
+    ));
+}
+
+// Offers each group of similarly named tasks for merging into the one the user picks.
+// Merged tasks are moved to the trash once every group has been seen.
+fn merge_duplicates(tasks: &mut Vec<Task>, config: &Config) {
+    let groups = find_similar(tasks, SIMILARITY_THRESHOLD);
+    if groups.is_empty() {
+        println!("\n{}", t("status.no_similar"));
+        return;
+    }
+
+    let mut merged = vec![];
+    for group in groups {
+        println!("\n{}", t("status.similar"));
+        for &i in &group {
+            println!("\t{}. {}", i + 1, tasks[i].name);
+        }
+        println!("\n{}", t("prompt.merge_keep"));
+        let Ok(keep) = read_line().parse::<usize>() else {
+            continue;
+        };
+        let keep = keep.wrapping_sub(1);
+        if !group.contains(&keep) {
+            println!("\n{}", t("error.merge_not_in_group"));
+            continue;
+        }
+
+        let others: Vec<usize> = group.into_iter().filter(|&i| i != keep).collect();
+        merge_tasks(tasks, keep, &others);
+        let count = others.len().to_string();
+        let values = [("count", count.as_str()), ("name", &tasks[keep].name)];
+        println!("\n{}", t_fill("status.merged", &values));
+        merged.extend(others);
+    }
+
+    // Removing from the end keeps the remaining indices valid.
+    merged.sort_unstable_by(|a, b| b.cmp(a));
+    for index in merged {
+        if let Err(error) = trash_task(tasks, index, &config.trash_path) {
+            println!(
+                "\n{}",
+                t_fill("error.trash_task", &[("error", &error.to_string())])
+            );
+        }
+    }
+}
+
+// Adds a subtask for each name, copying the due date, tags and priority of the task at
+// `index`. That task is then blocked by its subtasks, so it only shows as ready once they
+// are all done.
+fn split_task(tasks: &mut Vec<Task>, index: usize, names: Vec<String>) {
+    for name in names {
+        let subtask = Task {
+            name,
+            due_date: tasks[index].due_date.clone(),
+            tags: tasks[index].tags.clone(),
+            priority: tasks[index].priority,
+            ..Task::default()
+        };
+        add_task(tasks, subtask);
+
+        let id = tasks[tasks.len() - 1].id;
+        tasks[index].blocked_by.push(id);
+    }
+    log_action(&format!("split '{}' into subtasks", tasks[index].name));
+}
+
+// Returns an ID one higher than any in use, depended on, or handed out before, and records
+// it as taken. IDs start at 1, as 0 marks a task without one.
+fn next_id(tasks: &[Task]) -> u32 {
+    let highest = tasks
+        .iter()
+        .flat_map(|task| std::iter::once(&task.id).chain(&task.blocked_by))
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(HIGHEST_ID.load(AtomicOrdering::Relaxed));
+    HIGHEST_ID.store(highest + 1, AtomicOrdering::Relaxed);
+    highest + 1
+}
+
+// Notes the IDs of archived, deleted and focused tasks as taken, so dependencies, the focus
+// list and `done <id>` never end up pointing at a new task that reused one.
+fn reserve_retired_ids(config: &Config) {
+    for path in [&config.archive_path, &config.trash_path] {
+        let highest = read_tasks_or_empty(path)
+            .unwrap_or_default()
+            .iter()
+            .map(|task| task.id)
+            .max()
+            .unwrap_or(0);
+        HIGHEST_ID.fetch_max(highest, AtomicOrdering::Relaxed);
+    }
+    let focused = read_session(SESSION_PATH).focus.into_iter().max();
+    HIGHEST_ID.fetch_max(focused.unwrap_or(0), AtomicOrdering::Relaxed);
+}
+
+// Returns the current time, nudged forward if needed so it is later than every existing
+// task's creation time. Tasks added faster than the clock ticks still get distinct times.
+fn unique_timestamp(tasks: &[Task]) -> DateTime<Local> {
+    let now = Local::now();
+    match tasks.iter().filter_map(|task| task.created_at).max() {
+        Some(latest) if latest >= now => latest + Duration::nanoseconds(1),
+        _ => now,
+    }
+}
+
+// Gives an ID to every task loaded from a file written before tasks had them.
+fn assign_missing_ids(tasks: &mut [Task]) {
+    for i in 0..tasks.len() {
+        if tasks[i].id == 0 {
+            tasks[i].id = next_id(tasks);
+        }
+    }
+}
+
+// Gives a new ID to every task whose ID is already used by an earlier task, as can happen
+// when task files are merged by hand. Returns how many tasks were given new IDs.
+fn dedupe_ids(tasks: &mut [Task]) -> usize {
+    let mut seen: Vec<u32> = vec![];
+    let mut fixed = 0;
+
+    for i in 0..tasks.len() {
+        if seen.contains(&tasks[i].id) {
+            tasks[i].id = next_id(tasks);
+            log_action(&format!("gave '{}' new ID #{}", tasks[i].name, tasks[i].id));
+            fixed += 1;
+        }
+        seen.push(tasks[i].id);
+    }
+
+    fixed
+}
+
+// Creates a new task by prompting the user for its name, description, and due date.
+// Tags are matched against `known_tags` so existing ones are reused rather than duplicated.
+fn create_task(config: &Config, known_tags: &[String]) -> Task {
+    println!("\n{}", t("prompt.new_name"));
+    let name: String = read_line();
+    let named = [("name", name.as_str()), ("format", &config.date_format)];
+
+    println!("\n{}", t_fill("prompt.new_desc", &named));
+    let mut notes: String = String::new();
+    let desc: String = read_description(config.max_desc_length, &mut notes);
+
+    println!("\n{}", t_fill("prompt.due_date", &named));
+    let mut due_date: String = read_date(&config.date_format, config.require_due_date);
+
+    println!("\n{}", t("prompt.remind_days"));
+    let remind_before_days: Option<i64> = read_remind_days();
+
+    println!("\n{}", t_fill("prompt.weight", &named));
+    let weight: u32 = read_line()
+        .parse::<u32>()
+        .unwrap_or_else(|_| default_weight());
+
+    println!("\n{}", t_fill("prompt.estimate", &named));
+    let estimate_minutes: Option<u32> = read_line().parse::<u32>().ok();
+
+    println!("\n{}", t_fill("prompt.priority", &named));
+    let priority: Option<Priority> = parse_priority(&read_line());
+
+    println!("\n{}", t_fill("prompt.tags", &named));
+    if !known_tags.is_empty() {
+        let tags = known_tags.join(", ");
+        println!("{}", t_fill("status.existing_tags", &[("tags", &tags)]));
+    }
+    let tags: Vec<String> = parse_tags(&read_line())
+        .into_iter()
+        .map(|tag| complete_tag(tag, known_tags))
+        .collect();
+
+    println!("\n{}", t_fill("prompt.link", &named));
+    let link: Option<String> = read_link();
+
+    // Fields left blank fall back to the defaults of the task's tags.
+    let defaults = tag_defaults_for(&tags, config);
+    let priority: Priority = priority.or(defaults.priority).unwrap_or_default();
+    if let (true, Some(days)) = (due_date.is_empty(), defaults.due_in_days) {
+        match add_days(Local::now().date_naive(), days) {
+            Some(due) => due_date = due.format(DATE_FORMAT).to_string(),
+            None => {
+                let days = days.to_string();
+                println!(
+                    "\n{}",
+                    t_fill("error.default_due_out_of_range", &[("days", &days)])
+                );
+            }
+        }
+    }
+
+    Task {
+        name,
+        desc,
+        due_date,
+        remind_before_days,
+        weight,
+        priority,
+        tags,
+        notes,
+        estimate_minutes,
+        link,
+        ..Task::default()
+    }
+}
+// Combines the configured defaults of each tag, with earlier tags taking precedence.
+fn tag_defaults_for(tags: &[String], config: &Config) -> TagDefaults {
+    let mut combined = TagDefaults::default();
+    for defaults in tags.iter().filter_map(|tag| config.tag_defaults.get(tag)) {
+        combined.priority = combined.priority.or(defaults.priority);
+        combined.due_in_days = combined.due_in_days.or(defaults.due_in_days);
+    }
+    combined
+}
+
+// Reads a URL from the user, re-prompting until it looks like one or is left blank.
+fn read_link() -> Option<String> {
+    loop {
+        let input = read_line();
+        if input.is_empty() {
+            return None;
+        }
+        if is_url(&input) {
+            return Some(input);
+        }
+        println!("\n{}", t("error.invalid_link"));
+    }
+}
+
+// Reads how many days before its due date a task should be flagged, re-prompting while the
+// number is negative or reaches back past the earliest date that can be stored. Anything
+// other than a number keeps the default.
+fn read_remind_days() -> Option<i64> {
+    loop {
+        let days = read_line().parse::<i64>().ok()?;
+        let today = Local::now().date_naive();
+        if days >= 0 && add_days(today, -days).is_some() {
+            return Some(days);
+        }
+        println!("\n{}", t("error.remind_days"));
+    }
+}
+
+// Checks that text looks like a web URL: an http or https scheme followed by a host.
+fn is_url(text: &str) -> bool {
+    let rest = text
+        .strip_prefix("https://")
+        .or_else(|| text.strip_prefix("http://"));
+    rest.is_some_and(|rest| !rest.is_empty() && !rest.contains(char::is_whitespace))
+}
+
+// Matches an entered tag against the existing ones. A tag differing only in case takes the
+// existing spelling, and one that starts exactly one existing tag is offered its completion.
+// Anything else is kept as a new tag.
+fn complete_tag(tag: String, known_tags: &[String]) -> String {
+    let lower = tag.to_lowercase();
+    if let Some(known) = known_tags
+        .iter()
+        .find(|known| known.to_lowercase() == lower)
+    {
+        return known.clone();
+    }
+
+    let mut candidates = known_tags
+        .iter()
+        .filter(|known| known.to_lowercase().starts_with(&lower));
+    match (candidates.next(), candidates.next()) {
+        (Some(known), None)
+            if confirm(&t_fill(
+                "prompt.use_known_tag",
+                &[("known", known), ("tag", &tag)],
+            )) =>
+        {
+            known.clone()
+        }
+        _ => tag,
+    }
+}
+
+// Splits comma-separated input into trimmed, non-empty tags.
+fn parse_tags(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+// Adds a tag to each selected task that doesn't already have it, returning how many changed.
+fn add_tag_to_tasks(tasks: &mut [Task], indices: &[usize], tag: &str) -> usize {
+    let mut updated = 0;
+    for &index in indices {
+        if let Some(task) = tasks.get_mut(index) {
+            if !task.tags.iter().any(|existing| existing == tag) {
+                task.tags.push(tag.to_string());
+                log_action(&format!("tagged '{}' with '{}'", task.name, tag));
+                updated += 1;
+            }
+        }
+    }
+    updated
+}
+
+// Builds a filter by prompting for each condition, ignoring any left blank.
+fn read_filter(config: &Config) -> Filter {
+    let mut filter = Filter::default();
+
+    println!("\n{}", t("prompt.filter_done"));
+    filter.done = match read_line().to_lowercase().as_str() {
+        "pending" | "p" => Some(false),
+        "done" | "d" => Some(true),
+        _ => None,
+    };
+
+    println!("\n{}", t("prompt.filter_tag"));
+    let tag = read_line();
+    if !tag.is_empty() {
+        filter.tag = Some(tag);
+    }
+
+    println!("\n{}", t("prompt.filter_keyword"));
+    let keyword = read_line();
+    if !keyword.is_empty() {
+        filter.keyword = Some(keyword);
+    }
+
+    println!("\n{}", t("prompt.filter_priority"));
+    filter.priority = parse_priority(&read_line());
+
+    let values = [("format", config.date_format.as_str())];
+    println!("\n{}", t_fill("prompt.filter_due_before", &values));
+    let due_before = read_date(&config.date_format, false);
+    filter.due_before = NaiveDate::parse_from_str(&due_before, DATE_FORMAT).ok();
+
+    filter
+}
+
+// Prompts for new values for a task's fields, keeping any that are left blank.
+// The due date can also be cleared by entering "none".
+fn edit_task(task: &mut Task, config: &Config) {
+    println!("\n{}", t_fill("prompt.edit_name", &[("name", &task.name)]));
+    let name = read_line();
+    if !name.is_empty() {
+        task.name = name;
+    }
+
+    println!("\n{}", t("prompt.edit_desc"));
+    let desc = read_description(config.max_desc_length, &mut task.notes);
+    if !desc.is_empty() {
+        task.desc = desc;
+    }
+
+    let values = [("format", config.date_format.as_str())];
+    println!("\n{}", t_fill("prompt.edit_due_date", &values));
+    loop {
+        let input = read_line();
+        if input.is_empty() {
+            break;
+        }
+        if input.eq_ignore_ascii_case("none") {
+            task.due_date.clear();
+            break;
+        }
+
+        match NaiveDate::parse_from_str(&input, &config.date_format) {
+            Ok(date) => {
+                task.due_date = date.format(DATE_FORMAT).to_string();
+                break;
+            }
+            Err(_) => println!("\n{}", t_fill("error.edit_due_date", &values)),
+        }
+    }
+
+    println!("\n{}", t("prompt.edit_priority"));
+    if let Some(priority) = parse_priority(&read_line()) {
+        task.priority = priority;
+    }
+
+    println!("\n{}", t("prompt.edit_estimate"));
+    if let Ok(minutes) = read_line().parse::<u32>() {
+        task.estimate_minutes = Some(minutes);
+    }
+
+    println!("\n{}", t_fill("status.updated", &[("name", &task.name)]));
+    log_action(&format!("edited '{}'", task.name));
+}
+
+// Creates a new, undated task with the fields preset by a template.
+fn task_from_template(tmpl: &Template) -> Task {
+    Task {
+        name: tmpl.name.clone(),
+        desc: tmpl.desc.clone(),
+        priority: tmpl.priority,
+        recurrence: tmpl.recurrence,
+        ..Task::default()
+    }
+}
+
+// Creates a template with the name, description, priority and recurrence of a task.
+fn template_from_task(task: &Task) -> Template {
+    Template {
+        name: task.name.clone(),
+        desc: task.desc.clone(),
+        priority: task.priority,
+        recurrence: task.recurrence,
+    }
+}
+
+// Adds a template to the config file at `path`. The file is re-read first, so settings
+// adjusted at runtime, such as expanded paths, aren't written back.
+fn save_template(template: &Template, path: &str) -> Result<()> {
+    let mut saved = match read_config(path) {
+        Err(error) if error.is_io(io::ErrorKind::NotFound) => Config::default(),
+        result => result?,
+    };
+    saved.templates.push(template.clone());
+    save_config(&saved, path)
+}
+
+// Makes a task repeat. A completed or undated task is reopened and scheduled one period
+// from today, so it comes around again.
+fn make_recurring(task: &mut Task, recurrence: Recurrence) {
+    if task.done || parse_due_date(task).is_none() {
+        let today = Local::now().date_naive();
+        let Some(next_due) = next_due_date(today, recurrence) else {
+            let today = today.format(DATE_FORMAT).to_string();
+            let values = [("name", task.name.as_str()), ("date", &today)];
+            println!("\n{}", t_fill("error.next_due_out_of_range", &values));
+            return;
+        };
+        task.done = false;
+        task.in_progress = false;
+        task.completed_at = None;
+        task.due_date = next_due.format(DATE_FORMAT).to_string();
+    }
+    task.recurrence = Some(recurrence);
+    let recurrence_text = recurrence.to_string();
+    let values = [
+        ("name", task.name.as_str()),
+        ("recurrence", &recurrence_text),
+        ("date", &task.due_date),
+    ];
+    println!("\n{}", t_fill("status.now_recurring", &values));
+    log_action(&format!("made '{}' repeat {recurrence}", task.name));
+}
+
+// Tells the user there is nothing to select when the list is empty, returning whether it is.
+fn no_tasks(tasks: &[Task]) -> bool {
+    if tasks.is_empty() {
+        println!("\n{}", t("error.no_tasks"));
+    }
+    tasks.is_empty()
+}
+
+// Reads user input for the task index and returns it as an `Option<usize>`.
+// If the input is invalid or out of range, it says why and returns `None`.
+fn read_index_input(tasks: &[Task]) -> Option<usize> {
+    match parse_index(&read_line(), tasks.len()) {
+        Ok(index) => Some(index),
+        Err(message) => {
+            println!("\n{message}");
+            None
+        }
+    }
+}
+
+// Converts a task number typed by the user into a zero-based index into a list of `len`
+// tasks. Otherwise explains what is wrong: that it isn't a number, is below 1, or is past
+// the end of the list.
+fn parse_index(input: &str, len: usize) -> std::result::Result<usize, String> {
+    if input.trim().is_empty() {
+        return Err(t("error.index_blank").to_string());
+    }
+    let Ok(num) = input.trim().parse::<i64>() else {
+        return Err(t_fill("error.invalid_index_input", &[("input", input)]));
+    };
+
+    let num_text = num.to_string();
+    match usize::try_from(num) {
+        Ok(0) | Err(_) => Err(t_fill("error.index_below_one", &[("num", &num_text)])),
+        Ok(num) if num > len => Err(t_fill(
+            "error.index_out_of_range",
+            &[("num", &num_text), ("len", &len.to_string())],
+        )),
+        Ok(num) => Ok(num - 1),
+    }
+}
+
+// Reads a selection of several task indexes, such as "1,3" or "2-4", and returns them
+// zero-based without duplicates. If any part is invalid or out of range, it returns `None`.
+fn read_indices_input(tasks: &[Task]) -> Option<Vec<usize>> {
+    let mut indices: Vec<usize> = vec![];
+
+    for part in read_line()
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim().parse::<usize>(), end.trim().parse::<usize>()),
+            None => (part.parse::<usize>(), part.parse::<usize>()),
+        };
+        let (Ok(start), Ok(end)) = (start, end) else {
+            println!("\n{}", t("error.invalid_indexes"));
+            return None;
+        };
+        if start == 0 || start > end || end > tasks.len() {
+            println!("\n{}", t("error.invalid_task_index"));
+            return None;
+        }
+
+        for num in start..=end {
+            if !indices.contains(&(num - 1)) {
+                indices.push(num - 1);
+            }
+        }
+    }
+
+    if indices.is_empty() {
+        println!("\n{}", t("error.none_selected"));
+        return None;
+    }
+    Some(indices)
+}
+
+// Reads a description, and if it is longer than `max_len` characters asks whether to keep
+// it, truncate it, or move the full text into `notes` leaving a truncated description.
+fn read_description(max_len: usize, notes: &mut String) -> String {
+    let desc = read_line();
+    let len = desc.chars().count();
+    if len <= max_len {
+        return desc;
+    }
+
+    let (len, max) = (len.to_string(), max_len.to_string());
+    let values = [("len", len.as_str()), ("max", &max)];
+    println!("\n{}", t_fill("prompt.long_desc", &values));
+    match read_line().as_str() {
+        "2" => truncate_display(&desc, max_len),
+        "3" => {
+            let summary = truncate_display(&desc, max_len);
+            if !notes.is_empty() {
+                notes.push('\n');
+            }
+            notes.push_str(&desc);
+            summary
+        }
+        _ => desc,
+    }
+}
+
+// Reads a date in `date_format` from the user, re-prompting until it is valid or left
+// blank. A `required` date can't be left blank. Valid dates are returned in `DATE_FORMAT`
+// for storage.
+fn read_date(date_format: &str, required: bool) -> String {
+    loop {
+        let input = read_line();
+        if input.is_empty() && !required {
+            return input;
+        }
+
+        match NaiveDate::parse_from_str(&input, date_format) {
+            Ok(date) => return date.format(DATE_FORMAT).to_string(),
+            Err(_) if required => {
+                let values = [("format", date_format)];
+                println!("\n{}", t_fill("error.due_date_required", &values));
+            }
+            Err(_) => println!("\n{}", t_fill("error.due_date", &[("format", date_format)])),
+        }
+    }
+}
+
+// Asks a yes/no question, returning true only if the user answers yes.
+fn confirm(prompt: &str) -> bool {
+    println!("\n{}", t_fill("prompt.yes_no", &[("question", prompt)]));
+    matches!(read_line().to_lowercase().as_str(), "y" | "yes")
+}
+
+// Prints the main menu, numbering each option.
+fn print_menu() {
+    println!("\n{}", t("prompt.menu"));
+    for (i, (_, label)) in MENU.iter().enumerate() {
+        println!("{}. {}", i + 1, t(label));
+    }
+}
+
+// Maps menu input to an action, accepting either an option's number or a command word
+// such as "add" or "list". Returns `None` for anything else.
+fn parse_menu_choice(input: &str) -> Option<MenuChoice> {
+    if let Ok(num) = input.parse::<usize>() {
+        return MENU.get(num.wrapping_sub(1)).map(|(choice, _)| *choice);
+    }
+
+    match input.to_lowercase().as_str() {
+        "view" | "list" | "ls" => Some(MenuChoice::ViewTasks),
+        "add" | "new" => Some(MenuChoice::AddTask),
+        "done" | "complete" => Some(MenuChoice::CompleteTask),
+        "delete" | "remove" | "rm" => Some(MenuChoice::DeleteTask),
+        "toggle" => Some(MenuChoice::ToggleByName),
+        "progress" | "stats" => Some(MenuChoice::ShowProgress),
+        "details" | "show" => Some(MenuChoice::ViewDetails),
+        "next" => Some(MenuChoice::NextTask),
+        "restore" => Some(MenuChoice::RestoreTask),
+        "sort" => Some(MenuChoice::SortTasks),
+        "copy" => Some(MenuChoice::CopyTask),
+        "template" => Some(MenuChoice::NewFromTemplate),
+        "tag" => Some(MenuChoice::BulkTag),
+        "agenda" | "week" => Some(MenuChoice::WeekAgenda),
+        "edit" => Some(MenuChoice::EditTask),
+        "report" => Some(MenuChoice::CompletionReport),
+        "filter" => Some(MenuChoice::FilterTasks),
+        "pin" | "unpin" => Some(MenuChoice::TogglePin),
+        "depend" => Some(MenuChoice::AddDependency),
+        "tags" => Some(MenuChoice::ViewByTag),
+        "today" => Some(MenuChoice::CompletedToday),
+        "reverse" => Some(MenuChoice::ReverseOrder),
+        "blocked" => Some(MenuChoice::ToggleBlocked),
+        "export" => Some(MenuChoice::ExportCsv),
+        "import" => Some(MenuChoice::ImportCsv),
+        "split" => Some(MenuChoice::SplitTask),
+        "time" | "focus" => Some(MenuChoice::LogTime),
+        "estimates" => Some(MenuChoice::EstimateReport),
+        "prune" => Some(MenuChoice::PruneDependencies),
+        "review" => Some(MenuChoice::Review),
+        "priority" => Some(MenuChoice::SetPriority),
+        "weekly" => Some(MenuChoice::WeeklyReport),
+        "shift" | "postpone" => Some(MenuChoice::ShiftAll),
+        "open" | "link" => Some(MenuChoice::OpenLink),
+        "recur" | "repeat" => Some(MenuChoice::MakeRepeatable),
+        "dates" => Some(MenuChoice::BulkEditDates),
+        "forecast" => Some(MenuChoice::Forecast),
+        "status" | "start" => Some(MenuChoice::SetStatus),
+        "random" | "surprise" => Some(MenuChoice::SurpriseMe),
+        "calendar" | "cal" => Some(MenuChoice::Calendar),
+        "reset" => Some(MenuChoice::ResetAll),
+        "switch" => Some(MenuChoice::SwitchFile),
+        "group" => Some(MenuChoice::GroupByTag),
+        "note" => Some(MenuChoice::AddNote),
+        "notes" => Some(MenuChoice::ViewNotes),
+        "undated" | "someday" => Some(MenuChoice::ScheduleUndated),
+        "plan" => Some(MenuChoice::ToggleFocus),
+        "focused" => Some(MenuChoice::ViewFocus),
+        "duplicates" | "merge" => Some(MenuChoice::MergeDuplicates),
+        "snapshot" => Some(MenuChoice::Snapshot),
+        "triage" => Some(MenuChoice::BulkPriority),
+        "reorder" | "move" => Some(MenuChoice::Reorder),
+        "quit" | "exit" | "q" => Some(MenuChoice::Quit),
+        _ => None,
+    }
+}
+
+// Creates a line editor with the saved history when stdin is a terminal. Piped or
+// scripted input gets `None`, so it is read with `read_line` as before.
+fn open_editor() -> Option<DefaultEditor> {
+    if !stdin().is_terminal() {
+        return None;
+    }
+
+    let mut editor = DefaultEditor::new().ok()?;
+    // A missing history file just means this is the first session.
+    let _ = editor.load_history(HISTORY_PATH);
+    Some(editor)
+}
+
+// Reads a menu choice, with arrow-key history and line editing when an editor is available.
+// Returns `None` once input runs out, such as on Ctrl-D. Ctrl-C reads as an empty choice.
+fn read_menu_input(editor: &mut Option<DefaultEditor>) -> Option<String> {
+    let Some(editor) = editor else {
+        let mut input = String::new();
+        return match stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(input.trim().to_string()),
+        };
+    };
+
+    match editor.readline("") {
+        Ok(line) => {
+            let line = line.trim().to_string();
+            if !line.is_empty() {
+                let _ = editor.add_history_entry(line.as_str());
+            }
+            Some(line)
+        }
+        Err(ReadlineError::Interrupted) => Some(String::new()),
+        Err(_) => None,
+    }
+}
+
+// Reads a file path from the user, expanding `~` and environment variables.
+fn read_path() -> String {
+    expand_path(&read_line()).display().to_string()
+}
+
+// Reads a line of input from the user.
+fn read_line() -> String {
+    let mut input: String = String::new();
+    match stdin().read_line(&mut input) {
+        Ok(_) => {
+            input = input.trim().to_string();
+        }
+        Err(error) => {
+            eprintln!(
+                "{}",
+                t_fill("error.message", &[("error", &error.to_string())])
+            );
+        }
+    }
+
+    input
+}
+
+// Displays the list of tasks to the user.
+fn view_tasks(tasks: &[Task], config: &Config) {
+    view_tasks_where(tasks, config, |_| true);
+}
+
+// Displays only the tasks that `keep` accepts, numbered by their position in the full list
+// so the numbers can still be used to select them.
+// Long names are truncated to the configured width so the list stays aligned.
+fn view_tasks_where(tasks: &[Task], config: &Config, keep: impl Fn(&Task) -> bool) {
+    let today = Local::now().date_naive();
+    let line_width = terminal_width().saturating_sub(display_width(&config.indent));
+    let mut templates = config.line_format.lines();
+    let heading_template = templates.next().unwrap_or_default();
+    let detail_templates: Vec<&str> = templates.collect();
+    let mut shown = 0;
+    println!(); // newline
+
+    for (i, task) in tasks.iter().enumerate() {
+        if !keep(task) {
+            continue;
+        }
+        shown += 1;
+
+        let number = (i + 1).to_string();
+        let icon = format!(
+            "{}{}",
+            status_icon(task, today, config.icons),
+            if task.pinned { "★ " } else { "" }
+        );
+        let date = display_date(&task.due_date, &config.date_format);
+        let status = status_marker(task, config);
+        let tags = if task.tags.is_empty() {
+            String::new()
+        } else {
+            format!(" : #{}", task.tags.join(" #"))
+        };
+        let mut values = [
+            ("number", number.as_str()),
+            ("icon", icon.as_str()),
+            ("name", ""),
+            ("date", date.as_str()),
+            ("status", status),
+            ("tags", ""),
+            ("desc", task.desc.as_str()),
+        ];
+
+        // Narrow terminals shorten the name first, so the date and status stay visible.
+        let fixed_width = render_template(heading_template, &values).chars().count();
+        let name_width = config
+            .name_width
+            .min(line_width.saturating_sub(fixed_width))
+            .max(MIN_NAME_WIDTH);
+        let name = truncate_display(&task.name, name_width);
+        values[2].1 = &name;
+        values[5].1 = &tags;
+
+        let heading = render_template(heading_template, &values);
+        let heading = truncate_display(&heading, line_width.max(fixed_width + MIN_NAME_WIDTH));
+        let heading = if config.color {
+            colorize(&heading, task_color(task, today))
+        } else {
+            heading
+        };
+
+        println!("{}{}", config.indent, heading);
+        for template in &detail_templates {
+            println!("{}{}", config.indent, render_template(template, &values));
+        }
+        println!(); // newline
+    }
+
+    println!("{}{}", config.indent, format_footer(shown, tasks));
+}
+
+// Picks the configured marker for whether a task is pending, in progress, or done.
+fn status_marker<'a>(task: &Task, config: &'a Config) -> &'a str {
+    match task.status() {
+        Status::Todo => &config.pending_marker,
+        Status::InProgress => &config.in_progress_marker,
+        Status::Done => &config.done_marker,
+    }
+}
+
+// Fills each `{key}` placeholder in `template` with its value. Unknown placeholders are
+// kept as written, and values are inserted as-is, even if they contain braces.
+fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut rendered = String::new();
+    let mut rest = template;
+
+    while let Some(open) = rest.find('{') {
+        rendered.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let value = after.find('}').and_then(|close| {
+            let key = &after[..close];
+            values
+                .iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| (*value, close))
+        });
+
+        match value {
+            Some((value, close)) => {
+                rendered.push_str(value);
+                rest = &after[close + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = after;
+            }
+        }
+    }
+
+    rendered.push_str(rest);
+    rendered
+}
+
+// Counts the columns `text` takes up, with each tab taking `TAB_WIDTH`.
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+// Returns the width of the terminal in columns, or `DEFAULT_TERMINAL_WIDTH` if it can't
+// be detected.
+fn terminal_width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+// Maps days until a task is due onto a gradient from green (far away) to red (overdue).
+fn urgency_color(days_until: i64) -> Color {
+    match days_until {
+        i64::MIN..=-1 => Color::Red,
+        0..=1 => Color::Orange,
+        2..=6 => Color::Yellow,
+        _ => Color::Green,
+    }
+}
+
+// Picks a task's color: green once done, neutral without a due date, otherwise by urgency.
+fn task_color(task: &Task, today: NaiveDate) -> Color {
+    if task.done {
+        return Color::Green;
+    }
+
+    match parse_due_date(task) {
+        Some(due) if due < today => Color::Red,
+        _ if task.in_progress => Color::Cyan,
+        Some(due) => urgency_color((due - today).num_days()),
+        None => Color::Neutral,
+    }
+}
+
+// Wraps text in the ANSI escape codes for `color`.
+fn colorize(text: &str, color: Color) -> String {
+    let code = match color {
+        Color::Neutral => return text.to_string(),
+        Color::Green => "32",
+        Color::Yellow => "33",
+        Color::Orange => "38;5;208",
+        Color::Red => "31",
+        Color::Cyan => "36",
+    };
+    format!("\x1b[{code}m{text}\x1b[0m")
+}
+
+// Picks the icon for a task's status, including its trailing space, or nothing if icons are off.
+fn status_icon(task: &Task, today: NaiveDate, icons: IconSet) -> &'static str {
+    let (done, overdue, due_soon, pending) = match icons {
+        IconSet::Off => return "",
+        IconSet::Emoji => ("✅ ", "❗ ", "⏰ ", "⬜ "),
+        IconSet::Ascii => ("[x] ", "[!] ", "[~] ", "[ ] "),
+    };
+
+    if task.done {
+        done
+    } else if is_overdue(task, today) {
+        overdue
+    } else if is_due_soon(task, today) {
+        due_soon
+    } else {
+        pending
+    }
+}
+
+// Counts completed tasks per day ("2024-06-03") or ISO week ("2024-W23").
+fn completion_histogram(tasks: &[Task], bucket: Bucket) -> BTreeMap<String, usize> {
+    let mut histogram: BTreeMap<String, usize> = BTreeMap::new();
+
+    for completed_at in tasks.iter().filter_map(|task| task.completed_at) {
+        let key = match bucket {
+            Bucket::Day => completed_at.format("%Y-%m-%d").to_string(),
+            Bucket::Week => completed_at.format("%G-W%V").to_string(),
+        };
+        *histogram.entry(key).or_insert(0) += 1;
+    }
+
+    histogram
+}
+
+// Returns the tasks that were completed on `date`.
+fn completed_on(tasks: &[Task], date: NaiveDate) -> Vec<&Task> {
+    tasks
+        .iter()
+        .filter(|task| task.completed_at.is_some_and(|at| at.date_naive() == date))
+        .collect()
+}
+
+// Displays completion counts as a bar chart, one row per bucket.
+fn print_histogram(histogram: &BTreeMap<String, usize>) {
+    println!(); // newline
+
+    if histogram.is_empty() {
+        println!("\t{}", t("status.no_completed"));
+    }
+    for (bucket, count) in histogram {
+        println!("\t{} | {} {}", bucket, "#".repeat(*count), count);
+    }
+}
+
+// Counts how many tasks carry each distinct tag.
+fn tag_counts(tasks: &[Task]) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for tag in tasks.iter().flat_map(|task| &task.tags) {
+        *counts.entry(tag.clone()).or_insert(0) += 1;
+    }
+    counts
+}
+
+// Collects the tasks under each of their tags, so a task with several tags appears in each
+// group. Untagged tasks are grouped under the empty string, which no tag can be.
+fn group_by_tag(tasks: &[Task]) -> BTreeMap<String, Vec<&Task>> {
+    let mut groups: BTreeMap<String, Vec<&Task>> = BTreeMap::new();
+    for task in tasks {
+        if task.tags.is_empty() {
+            groups.entry(String::new()).or_default().push(task);
+        }
+        for tag in &task.tags {
+            groups.entry(tag.clone()).or_default().push(task);
+        }
+    }
+    groups
+}
+
+// Displays every tag as a heading with its tasks underneath, then the untagged tasks.
+// Tasks keep their numbers from the full list so they can still be selected.
+fn print_tag_groups(tasks: &[Task], config: &Config) {
+    if tasks.is_empty() {
+        println!("\n{}", t("status.no_tasks_yet"));
+        return;
+    }
+
+    let mut groups = group_by_tag(tasks);
+    let untagged = groups.remove("");
+    let groups = groups
+        .iter()
+        .map(|(tag, group)| (format!("#{tag}"), group))
+        .chain(
+            untagged
+                .as_ref()
+                .map(|group| (t("status.untagged").to_string(), group)),
+        );
+
+    for (heading, group) in groups {
+        println!("\n{heading} ({})", group.len());
+        for task in group {
+            let number = tasks
+                .iter()
+                .position(|other| std::ptr::eq(other, *task))
+                .map_or(0, |i| i + 1);
+            println!(
+                "{}{}. {} {} : {}",
+                config.indent,
+                number,
+                status_marker(task, config),
+                task.name,
+                display_date(&task.due_date, &config.date_format)
+            );
+        }
+    }
+}
+
+// Displays a numbered list of tags and their task counts, if any tasks are tagged.
+fn print_tag_legend(counts: &BTreeMap<String, usize>) {
+    if counts.is_empty() {
+        return;
+    }
+
+    let legend: Vec<String> = counts
+        .iter()
+        .enumerate()
+        .map(|(i, (tag, count))| format!("{}. #{} ({})", i + 1, tag, count))
+        .collect();
+    println!(
+        "\n{}",
+        t_fill("status.tag_legend", &[("tags", &legend.join("  "))])
+    );
+}
+
+// Summarizes how many of `all` tasks are on screen, and how many are pending or done.
+fn format_footer(shown: usize, all: &[Task]) -> String {
+    let done = all.iter().filter(|task| task.done).count();
+    let [shown, total, pending, done] =
+        [shown, all.len(), all.len() - done, done].map(|count| count.to_string());
+    t_fill(
+        "status.footer",
+        &[
+            ("shown", &shown),
+            ("total", &total),
+            ("pending", &pending),
+            ("done", &done),
+        ],
+    )
+}
+
+// Groups tasks due in the seven days from `week_start` by their due date. Every day of
+// the week has an entry, even if nothing is due that day.
+fn group_by_day(tasks: &[Task], week_start: NaiveDate) -> BTreeMap<NaiveDate, Vec<&Task>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&Task>> = (0..7)
+        .map(|offset| (week_start + Duration::days(offset), vec![]))
+        .collect();
+
+    for task in tasks {
+        if let Some(day_tasks) = parse_due_date(task).and_then(|due| days.get_mut(&due)) {
+            day_tasks.push(task);
+        }
+    }
+
+    days
+}
+
+// Displays the week as an agenda, with a heading per day and a final section for tasks
+// that are undated or due outside the week.
+fn view_week(tasks: &[Task], week_start: NaiveDate, config: &Config) {
+    let days = group_by_day(tasks, week_start);
+
+    for (day, day_tasks) in &days {
+        println!("\n{}", day.format("%A %d %B"));
+        for task in day_tasks {
+            let done = if task.done {
+                t("status.done_suffix")
+            } else {
+                ""
+            };
+            println!("\t{}{done}", task.name);
+        }
+    }
+
+    println!("\n{}", t("status.unscheduled"));
+    for task in tasks {
+        let in_week = parse_due_date(task).is_some_and(|due| days.contains_key(&due));
+        if !in_week {
+            let due = display_date(&task.due_date, &config.date_format);
+            let done = if task.done {
+                t("status.done_suffix")
+            } else {
+                ""
+            };
+            println!("\t{} ({due}){done}", task.name);
+        }
+    }
+}
+
+// Builds a plain-text summary of the week starting `week_start`, suitable for emailing:
+// tasks that were already overdue when the week began, pending tasks grouped by due date,
+// and the tasks completed during the week.
+fn weekly_report(tasks: &[Task], week_start: NaiveDate) -> String {
+    let week_end = week_start + Duration::days(7);
+    let week = week_start.format("%d %B %Y").to_string();
+    let mut report = t_fill("report.heading", &[("week", &week)]);
+    report.push('\n');
+
+    let pending: Vec<&Task> = tasks.iter().filter(|task| !task.done).collect();
+    let overdue: Vec<&&Task> = pending
+        .iter()
+        .filter(|task| parse_due_date(task).is_some_and(|due| due < week_start))
+        .collect();
+    if !overdue.is_empty() {
+        let count = overdue.len().to_string();
+        let _ = writeln!(
+            report,
+            "\n{}",
+            t_fill("report.overdue", &[("count", &count)])
+        );
+        for task in overdue {
+            let values = [("name", task.name.as_str()), ("date", &task.due_date)];
+            let _ = writeln!(report, "  - {}", t_fill("report.overdue_task", &values));
+        }
+    }
+
+    let mut by_date: BTreeMap<NaiveDate, Vec<&Task>> = BTreeMap::new();
+    let mut undated: Vec<&Task> = vec![];
+    for task in &pending {
+        match parse_due_date(task) {
+            Some(due) if due >= week_start => by_date.entry(due).or_default().push(task),
+            Some(_) => {}
+            None => undated.push(task),
+        }
+    }
+
+    let _ = writeln!(report, "\n{}", t("report.pending"));
+    if by_date.is_empty() && undated.is_empty() {
+        let _ = writeln!(report, "  {}", t("report.nothing_pending"));
+    }
+    for (date, date_tasks) in &by_date {
+        let _ = writeln!(report, "  {}", date.format("%A %d %B"));
+        for task in date_tasks {
+            let _ = writeln!(report, "    - {}", task.name);
+        }
+    }
+    if !undated.is_empty() {
+        let _ = writeln!(report, "  {}", t("report.no_due_date"));
+        for task in undated {
+            let _ = writeln!(report, "    - {}", task.name);
+        }
+    }
+
+    let _ = writeln!(report, "\n{}", t("report.completed"));
+    let completed: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| {
+            task.completed_at
+                .is_some_and(|at| (week_start..week_end).contains(&at.date_naive()))
+        })
+        .collect();
+    if completed.is_empty() {
+        let _ = writeln!(report, "  {}", t("report.nothing_completed"));
+    }
+    for task in completed {
+        let _ = writeln!(report, "  - {}", task.name);
+    }
+
+    report
+}
+
+// Draws a month as a calendar grid, with weeks starting on Monday. Days with pending tasks
+// due are marked with how many, such as "14*2".
+fn render_calendar(tasks: &[Task], year: i32, month: u32) -> String {
+    let Some(first) = NaiveDate::from_ymd_opt(year, month, 1) else {
+        return String::new();
+    };
+
+    let mut due_counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for due in tasks
+        .iter()
+        .filter(|task| !task.done)
+        .filter_map(parse_due_date)
+    {
+        *due_counts.entry(due).or_insert(0) += 1;
+    }
+
+    let mut calendar = format!("{:^42}\n", first.format("%B %Y").to_string());
+    for day in t("calendar.weekdays").split_whitespace() {
+        let _ = write!(calendar, " {day:<5}");
+    }
+    calendar.push('\n');
+
+    // Pads the first week out to the weekday the month starts on.
+    let offset = first.weekday().num_days_from_monday() as usize;
+    calendar.push_str(&" ".repeat(6 * offset));
+
+    let mut date = first;
+    while date.month() == month {
+        let marker = match due_counts.get(&date) {
+            Some(count) => format!("*{count}"),
+            None => String::new(),
+        };
+        let _ = write!(calendar, " {:>2}{:<3}", date.day(), marker);
+        if date.weekday().num_days_from_monday() == 6 {
+            calendar.push('\n');
+        }
+        date = date.succ_opt().unwrap_or(NaiveDate::MAX);
+    }
+    if !calendar.ends_with('\n') {
+        calendar.push('\n');
+    }
+
+    calendar
+}
+
+// Displays every field of a single task, with nothing truncated.
+fn view_task_details(task: &Task) {
+    let detail = |key: &str, value: &str| println!("\t{}", t_fill(key, &[("value", value)]));
+
+    let id = task.id.to_string();
+    println!();
+    println!(
+        "\t{}",
+        t_fill("detail.name", &[("name", &task.name), ("id", &id)])
+    );
+    detail("detail.description", &task.desc);
+    if !task.notes.is_empty() {
+        detail("detail.notes", &task.notes);
+    }
+    detail(
+        "detail.due_date",
+        &display_date(&task.due_date, DATE_FORMAT),
+    );
+    detail("detail.status", &task.status().to_string());
+    detail("detail.weight", &task.weight.to_string());
+    detail("detail.priority", &task.priority.to_string());
+    if task.pinned {
+        println!("\t{}", t("detail.pinned"));
+    }
+    if let Some(recurrence) = task.recurrence {
+        detail("detail.repeats", &recurrence.to_string());
+    }
+    if !task.tags.is_empty() {
+        detail("detail.tags", &task.tags.join(", "));
+    }
+    if !task.blocked_by.is_empty() {
+        let ids: Vec<String> = task.blocked_by.iter().map(|id| format!("#{id}")).collect();
+        detail("detail.blocked_by", &ids.join(", "));
+    }
+    if let Some(days) = task.remind_before_days {
+        detail("detail.remind", &days.to_string());
+    }
+    if let Some(link) = &task.link {
+        detail("detail.link", link);
+    }
+    let spent = minutes_human(task.time_spent_minutes);
+    match task.estimate_minutes {
+        Some(estimate) => {
+            let values = [
+                ("spent", spent.as_str()),
+                ("estimate", &minutes_human(estimate)),
+            ];
+            println!("\t{}", t_fill("detail.time_estimated", &values));
+        }
+        None if task.time_spent_minutes > 0 => detail("detail.time", &spent),
+        None => {}
+    }
+}
+
+// Formats a number of minutes as hours and minutes, such as "1h 15m" or "40m".
+fn minutes_human(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, mins) => format!("{mins}m"),
+        (hours, 0) => format!("{hours}h"),
+        (hours, mins) => format!("{hours}h {mins}m"),
+    }
+}
+
+// Shortens `s` to at most `max` characters, ending with an ellipsis if anything was cut.
+fn truncate_display(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    if max == 0 {
+        return String::new();
+    }
+
+    let mut truncated: String = s.chars().take(max - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+// Shows a stored due date in the user's date format, or as-is if it isn't a valid date.
+fn display_date(due_date: &str, date_format: &str) -> String {
+    if due_date.is_empty() {
+        return t("detail.no_due_date").to_string();
+    }
+
+    match NaiveDate::parse_from_str(due_date, DATE_FORMAT) {
+        Ok(date) => date.format(date_format).to_string(),
+        Err(_) => due_date.to_string(),
+    }
+}
+
+// Formats a task as plain text suitable for pasting elsewhere.
+fn format_task_for_clipboard(task: &Task) -> String {
+    let mut text = task.name.clone();
+    if !task.due_date.is_empty() {
+        text.push_str(&format!(" (due {})", task.due_date));
+    }
+    if !task.desc.is_empty() {
+        text.push_str(&format!("\n{}", task.desc));
+    }
+    text
+}
+
+// Puts text on the system clipboard, printing it instead if no clipboard is available.
+fn copy_to_clipboard(text: &str) {
+    match arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text)) {
+        Ok(()) => println!("\n{}", t("status.copied")),
+        Err(_) => println!("\n{}\n\n{text}", t("error.no_clipboard")),
+    }
+}
+
+// Parses a recurrence name (or its first letter), ignoring case.
+fn parse_recurrence(input: &str) -> Option<Recurrence> {
+    match input.to_lowercase().as_str() {
+        "d" | "daily" => Some(Recurrence::Daily),
+        "w" | "weekly" => Some(Recurrence::Weekly),
+        "m" | "monthly" => Some(Recurrence::Monthly),
+        _ => None,
+    }
+}
+
+// Parses a task status name (or its first letter), ignoring case.
+fn parse_status(input: &str) -> Option<Status> {
+    match input.to_lowercase().as_str() {
+        "t" | "todo" | "to do" => Some(Status::Todo),
+        "i" | "in progress" | "progress" | "started" => Some(Status::InProgress),
+        "d" | "done" => Some(Status::Done),
+        _ => None,
+    }
+}
+
+// Parses a priority name (or its first letter), ignoring case.
+fn parse_priority(input: &str) -> Option<Priority> {
+    match input.to_lowercase().as_str() {
+        "l" | "low" => Some(Priority::Low),
+        "m" | "medium" => Some(Priority::Medium),
+        "h" | "high" => Some(Priority::High),
+        _ => None,
+    }
+}
+
+// Gives every task matching `filter` the priority `p`, returning how many changed.
+fn set_priority_where(tasks: &mut [Task], filter: &Filter, p: Priority) -> usize {
+    let indices: Vec<usize> = (0..tasks.len())
+        .filter(|&i| filter.matches(&tasks[i]) && tasks[i].priority != p)
+        .collect();
+    for &index in &indices {
+        set_priority(tasks, index, p);
+    }
+    indices.len()
+}
+
+// Changes the priority of the task at `index`.
+fn set_priority(tasks: &mut [Task], index: usize, p: Priority) {
+    let Some(task) = tasks.get_mut(index) else {
+        println!("\n{}", t("error.invalid_task_index"));
+        return;
+    };
+
+    task.priority = p;
+    let priority = task.priority.to_string();
+    let values = [("name", task.name.as_str()), ("priority", &priority)];
+    println!("\n{}", t_fill("status.now_priority", &values));
+    log_action(&format!("set '{}' priority - {}", task.name, task.priority));
+}
+
+// Picks the most urgent incomplete task: overdue tasks first, then higher priority, then
+// the nearest due date. Undated tasks come after dated ones, and ties keep list order.
+fn pick_next(tasks: &[Task], today: NaiveDate) -> Option<&Task> {
+    tasks.iter().filter(|task| !task.done).min_by_key(|task| {
+        (
+            Reverse(is_overdue(task, today)),
+            Reverse(task.priority),
+            parse_due_date(task).unwrap_or(NaiveDate::MAX),
+        )
+    })
+}
+
+// Summarizes the list on one line for status bars, such as
+// "3 pending, 1 overdue, next: 'Submit report' due tomorrow".
+fn status_line(tasks: &[Task]) -> String {
+    let today = Local::now().date_naive();
+    let stats = compute_stats(tasks);
+    let overdue = tasks.iter().filter(|task| is_overdue(task, today)).count();
+
+    let (pending, overdue) = ((stats.total - stats.done).to_string(), overdue.to_string());
+    let counts = [("pending", pending.as_str()), ("overdue", &overdue)];
+    let mut line = t_fill("status.line", &counts);
+    if let Some(next) = pick_next(tasks, today) {
+        line.push_str(&t_fill("status.line_next", &[("name", &next.name)]));
+        if let Some(due) = parse_due_date(next) {
+            line.push_str(&format!(" {}", due_relative(due, today)));
+        }
+    }
+    line
+}
+
+// Describes when a due date falls relative to today, such as "due tomorrow" or
+// "2 days overdue".
+fn due_relative(due: NaiveDate, today: NaiveDate) -> String {
+    match (due - today).num_days() {
+        0 => t("status.due_today").to_string(),
+        1 => t("status.due_tomorrow").to_string(),
+        -1 => t("status.one_day_overdue").to_string(),
+        days if days < 0 => t_fill("status.days_overdue", &[("days", &(-days).to_string())]),
+        days => t_fill("status.due_in_days", &[("days", &days.to_string())]),
+    }
+}
+
+// Picks a pending task at random. With `by_priority`, high priority tasks are three times
+// as likely to be picked as low ones, and medium twice as likely. Taking the random number
+// generator as a parameter lets a seeded one give repeatable picks.
+fn random_task<'a>(tasks: &'a [Task], by_priority: bool, rng: &mut impl Rng) -> Option<&'a Task> {
+    let pending: Vec<&Task> = tasks.iter().filter(|task| !task.done).collect();
+    if !by_priority {
+        return pending.choose(rng).copied();
+    }
+
+    let weights = pending.iter().map(|task| match task.priority {
+        Priority::Low => 1,
+        Priority::Medium => 2,
+        Priority::High => 3,
+    });
+    let index = WeightedIndex::new(weights).ok()?.sample(rng);
+    pending.get(index).copied()
+}
+
+// Parses a sort menu choice, by number or name.
+fn parse_sort_key(input: &str) -> Option<SortKey> {
+    match input.to_lowercase().as_str() {
+        "1" | "name" => Some(SortKey::Name),
+        "2" | "due" | "due date" => Some(SortKey::DueDate),
+        "3" | "priority" => Some(SortKey::Priority),
+        _ => None,
+    }
+}
+
+// Sorts the tasks in place by the session's sort key, keeping the current order when no
+// key is chosen. Undated tasks sort after dated ones, and higher priorities come first.
+// The sorted order is flipped if `reverse` is set, then the list is partitioned as
+// `partition_tasks` describes.
+fn sort_tasks(tasks: &mut [Task], session: &SessionState, config: &Config) {
+    match session.sort_key {
+        Some(SortKey::Name) => tasks.sort_by(|a, b| natural_cmp(&a.name, &b.name)),
+        Some(SortKey::DueDate) => {
+            tasks.sort_by_key(|task| parse_due_date(task).unwrap_or(NaiveDate::MAX))
+        }
+        Some(SortKey::Priority) => tasks.sort_by_key(|task| Reverse(task.priority)),
+        None => {}
+    }
+
+    // Without a sort key the list is reversed once when toggled, rather than on every sort.
+    if session.reverse && session.sort_key.is_some() {
+        tasks.reverse();
+    }
+
+    partition_tasks(tasks, config);
+}
+
+// Moves completed tasks below pending ones when `done_last` is enabled, and pinned tasks
+// to the top, leaving the order within each group alone. Unlike the sort key this applies
+// whether or not `auto_sort` is on, so the list always shows in these groups.
+fn partition_tasks(tasks: &mut [Task], config: &Config) {
+    // A stable sort on each flag partitions the list without disturbing either half.
+    if config.done_last {
+        tasks.sort_by_key(|task| task.done);
+    }
+    tasks.sort_by_key(|task| !task.pinned);
+}
+
+// Checks the pending task count against `max_pending` before a task is added. The cap is
+// only advisory, so going over it just asks for confirmation.
+fn allow_another_task(tasks: &[Task], config: &Config) -> bool {
+    allow_more_tasks(tasks, config, 1)
+}
+
+// Checks whether `adding` more pending tasks would go over `max_pending`, asking once for
+// the whole batch if so.
+fn allow_more_tasks(tasks: &[Task], config: &Config, adding: usize) -> bool {
+    let Some(limit) = config.max_pending else {
+        return true;
+    };
+
+    let pending = tasks.iter().filter(|task| !task.done).count();
+    let total = pending.saturating_add(adding);
+    if adding == 0 || total <= limit {
+        return true;
+    }
+
+    let (pending_text, limit_text) = (pending.to_string(), limit.to_string());
+    if adding == 1 {
+        let values = [("pending", pending_text.as_str()), ("limit", &limit_text)];
+        return confirm(&t_fill("prompt.over_limit", &values));
+    }
+    let (adding_text, total_text) = (adding.to_string(), total.to_string());
+    let values = [
+        ("count", adding_text.as_str()),
+        ("total", &total_text),
+        ("limit", &limit_text),
+    ];
+    confirm(&t_fill("prompt.over_limit_batch", &values))
+}
+
+// Moves the task at `from` to position `to`, shifting the tasks in between along by one.
+fn move_task(tasks: &mut Vec<Task>, from: usize, to: usize) {
+    let task = tasks.remove(from);
+    tasks.insert(to.min(tasks.len()), task);
+}
+
+// Lets the user move tasks one at a time, showing the new order after each move, until
+// they finish or cancel. The list only changes if they finish, returning true.
+fn reorder_tasks(tasks: &mut Vec<Task>, config: &Config) -> bool {
+    let mut order = tasks.clone();
+    let mut moved = 0;
+
+    loop {
+        view_tasks(&order, config);
+        println!("\n{}", t("prompt.reorder_from"));
+        let input = read_line();
+        if input.is_empty() {
+            break;
+        }
+        if input.eq_ignore_ascii_case("c") {
+            println!("\n{}", t("status.reorder_cancelled"));
+            return false;
+        }
+        let from = match parse_index(&input, order.len()) {
+            Ok(from) => from,
+            Err(message) => {
+                println!("\n{message}");
+                continue;
+            }
+        };
+
+        println!(
+            "\n{}",
+            t_fill("prompt.reorder_to", &[("name", &order[from].name)])
+        );
+        match parse_index(&read_line(), order.len()) {
+            Ok(to) => {
+                move_task(&mut order, from, to);
+                moved += 1;
+            }
+            Err(message) => println!("\n{message}"),
+        }
+    }
+
+    if moved > 0 {
+        *tasks = order;
+        log_action(&format!("reordered tasks - {moved} move(s)"));
+        println!("\n{}", t("status.reorder_saved"));
+    }
+    moved > 0
+}
+
+// Re-applies the active sort after a change to the list, if `auto_sort` is enabled.
+// Otherwise tasks keep their places, apart from pinned and completed tasks moving to
+// their groups.
+fn auto_sort(tasks: &mut [Task], session: &SessionState, config: &Config) {
+    if config.auto_sort {
+        sort_tasks(tasks, session, config);
+    } else {
+        partition_tasks(tasks, config);
+    }
+}
+
+// Compares strings the way people expect numbered names to sort, case-insensitively and
+// with runs of digits compared by value, so "Item 2" comes before "Item 10".
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x_digits = take_digits(&mut a);
+                let y_digits = take_digits(&mut b);
+                let x_num = x_digits.trim_start_matches('0');
+                let y_num = y_digits.trim_start_matches('0');
+
+                // A longer number without leading zeros is larger; equal lengths compare
+                // digit by digit.
+                let ordering = x_num.len().cmp(&y_num.len()).then_with(|| x_num.cmp(y_num));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.to_lowercase().cmp(y.to_lowercase());
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+// Consumes and returns the run of ASCII digits at the front of `chars`.
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
+
+// Sets a task's due date to `offset_days` after the reference task's due date.
+// Returns why, leaving the task unchanged, if the reference task has no valid date or the
+// offset moves past the range of dates that can be stored.
+fn set_relative_due_date(
+    tasks: &mut [Task],
+    index: usize,
+    reference: usize,
+    offset_days: i64,
+) -> std::result::Result<(), String> {
+    let Some(reference_due) = parse_due_date(&tasks[reference]) else {
+        return Err(t_fill(
+            "error.no_reference_date",
+            &[("name", &tasks[reference].name)],
+        ));
+    };
+    let Some(due) = add_days(reference_due, offset_days) else {
+        let (days, date) = (offset_days.to_string(), reference_due.to_string());
+        let values = [("days", days.as_str()), ("date", &date)];
+        return Err(t_fill("error.offset_out_of_range", &values));
+    };
+
+    tasks[index].due_date = due.format(DATE_FORMAT).to_string();
+    log_action(&format!(
+        "rescheduled '{}' to {}",
+        tasks[index].name, tasks[index].due_date
+    ));
+    Ok(())
+}
+
+// Moves the due date of every overdue, incomplete task to `today`, returning how many moved.
+fn reschedule_overdue(tasks: &mut [Task], today: NaiveDate) -> usize {
+    let mut changed = 0;
+    for task in tasks.iter_mut().filter(|task| is_overdue(task, today)) {
+        task.due_date = today.format(DATE_FORMAT).to_string();
+        log_action(&format!("rescheduled '{}' to {}", task.name, task.due_date));
+        changed += 1;
+    }
+    changed
+}
+
+// Moves the due date of every incomplete task that has one by `days`, returning how many
+// moved. Undated tasks are left alone. If any date would move out of the range of dates
+// that can be stored, nothing is changed and `None` is returned.
+fn shift_due_dates(tasks: &mut [Task], days: i64) -> Option<usize> {
+    let mut moved = vec![];
+    for (i, task) in tasks.iter().enumerate().filter(|(_, task)| !task.done) {
+        if let Some(due) = parse_due_date(task) {
+            moved.push((i, add_days(due, days)?));
+        }
+    }
+
+    for &(i, due) in &moved {
+        let task = &mut tasks[i];
+        task.due_date = due.format(DATE_FORMAT).to_string();
+        log_action(&format!("moved '{}' to {}", task.name, task.due_date));
+    }
+    Some(moved.len())
+}
+
+// Adds `days` to `date`, or returns `None` if the result is out of the supported range.
+fn add_days(date: NaiveDate, days: i64) -> Option<NaiveDate> {
+    TimeDelta::try_days(days).and_then(|delta| date.checked_add_signed(delta))
+}
+
+// Opens the pending tasks' due dates in `$EDITOR` as "index<TAB>due date<TAB>name" lines,
+// then applies whatever dates were changed. Lines that can't be read are reported and
+// skipped, and a blank date clears it.
+fn bulk_edit_due_dates(tasks: &mut [Task]) -> Result<()> {
+    let path = env::temp_dir().join(format!("todo-due-dates-{}.txt", std::process::id()));
+    let mut contents = String::new();
+    for line in t_fill("editor.dates_header", &[("format", DATE_FORMAT)]).lines() {
+        let _ = writeln!(contents, "# {line}");
+    }
+    for (i, task) in tasks.iter().enumerate().filter(|(_, task)| !task.done) {
+        let _ = writeln!(contents, "{}\t{}\t{}", i + 1, task.due_date, task.name);
+    }
+    fs::write(&path, contents)?;
+
+    let edited = run_editor(&path).and_then(|()| Ok(fs::read_to_string(&path)?));
+    let _ = fs::remove_file(&path);
+    let edited = edited?;
+
+    let mut changed = 0;
+    for (i, line) in edited.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut fields = line.split('\t');
+        let index = fields
+            .next()
+            .and_then(|index| index.trim().parse::<usize>().ok());
+        let Some(task) = index.and_then(|index| tasks.get_mut(index.wrapping_sub(1))) else {
+            let line = (i + 1).to_string();
+            println!(
+                "\t{}",
+                t_fill("error.dates_line_number", &[("line", &line)])
+            );
+            continue;
+        };
+
+        let due_date = fields.next().unwrap_or_default().trim();
+        if !due_date.is_empty() && NaiveDate::parse_from_str(due_date, DATE_FORMAT).is_err() {
+            let line = (i + 1).to_string();
+            let values = [
+                ("line", line.as_str()),
+                ("found", due_date),
+                ("format", DATE_FORMAT),
+            ];
+            println!("\t{}", t_fill("error.dates_line_date", &values));
+            continue;
+        }
+
+        if task.due_date != due_date {
+            task.due_date = due_date.to_string();
+            log_action(&format!("set '{}' due date - {}", task.name, due_date));
+            changed += 1;
+        }
+    }
+
+    let changed = changed.to_string();
+    println!(
+        "\n{}",
+        t_fill("status.dates_changed", &[("count", &changed)])
+    );
+    Ok(())
+}
+
+// Opens `path` in the user's editor from `$VISUAL` or `$EDITOR`, waiting for it to close.
+fn run_editor(path: &Path) -> Result<()> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+
+    // Editors are often configured with arguments, such as "code --wait".
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(TodoError::Editor { editor, status })
+    }
+}
+
+// Projects when each pending task with an estimate could be finished, working through
+// them in list order for `daily_minutes` a day from `start`. Time already spent on a task
+// is taken off its estimate. Returns each task's index with its projected date, or `None`
+// once the projection runs past the last date that can be represented.
+fn forecast(
+    tasks: &[Task],
+    daily_minutes: u32,
+    start: NaiveDate,
+) -> Vec<(usize, Option<NaiveDate>)> {
+    if daily_minutes == 0 {
+        return vec![];
+    }
+
+    let mut total: u64 = 0;
+    let mut projected = vec![];
+    for (i, task) in tasks.iter().enumerate().filter(|(_, task)| !task.done) {
+        let Some(estimate) = task.estimate_minutes else {
+            continue;
+        };
+
+        total = total.saturating_add(u64::from(estimate.saturating_sub(task.time_spent_minutes)));
+        let days = total.saturating_sub(1) / u64::from(daily_minutes);
+        let date = i64::try_from(days)
+            .ok()
+            .and_then(|days| add_days(start, days));
+        projected.push((i, date));
+    }
+    projected
+}
+
+// Parses a task's due date, returning `None` if it is blank or not in `DATE_FORMAT`.
+fn parse_due_date(task: &Task) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&task.due_date, DATE_FORMAT).ok()
+}
+
+// Checks whether an incomplete task's due date has already passed.
+fn is_overdue(task: &Task, today: NaiveDate) -> bool {
+    match parse_due_date(task) {
+        Some(due) => !task.done && due < today,
+        None => false,
+    }
+}
+
+// Checks whether an incomplete task is within its reminder lead time of being due.
+fn is_due_soon(task: &Task, today: NaiveDate) -> bool {
+    let lead_time = task.remind_before_days.unwrap_or(DUE_SOON_DAYS);
+    match parse_due_date(task) {
+        // A lead time reaching back past the earliest storable date covers every day.
+        Some(due) => {
+            let flagged_from = lead_time.checked_neg().and_then(|days| add_days(due, days));
+            !task.done && due >= today && flagged_from.is_none_or(|from| today >= from)
+        }
+        None => false,
+    }
+}
+
+// Lists overdue and soon-due tasks when the program starts. With `bell` set, anything
+// overdue also rings the terminal bell, unless output isn't going to a terminal.
+fn print_due_banner(tasks: &[Task], today: NaiveDate, bell: bool) {
+    let mut any_overdue = false;
+    for task in tasks {
+        let values = [("name", task.name.as_str()), ("date", &task.due_date)];
+        if is_overdue(task, today) {
+            println!("{}", t_fill("status.banner_overdue", &values));
+            any_overdue = true;
+        } else if is_due_soon(task, today) {
+            println!("{}", t_fill("status.banner_due_soon", &values));
+        }
+    }
+
+    if bell && any_overdue && io::stdout().is_terminal() {
+        print!("\x07");
+        let _ = io::stdout().flush();
+    }
+}
+
+// Counts tasks and their weights, in total and for completed tasks only.
+fn compute_stats(tasks: &[Task]) -> Stats {
+    let mut stats = Stats {
+        total: tasks.len(),
+        done: 0,
+        total_weight: 0,
+        done_weight: 0,
+    };
+
+    for task in tasks {
+        stats.total_weight += u64::from(task.weight);
+        if task.done {
+            stats.done += 1;
+            stats.done_weight += u64::from(task.weight);
+        }
+    }
+
+    stats
+}
+
+// Returns `part` as a percentage of `whole`, treating an empty whole as no progress.
+fn percentage(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+// Displays how far through the task list the user is, by count and by weight.
+fn print_stats(stats: &Stats) {
+    let (done, total) = (stats.done.to_string(), stats.total.to_string());
+    let percent = format!("{:.0}", percentage(stats.done as u64, stats.total as u64));
+    let values = [
+        ("done", done.as_str()),
+        ("total", &total),
+        ("percent", &percent),
+    ];
+    println!("\n\t{}", t_fill("status.progress", &values));
+
+    let (done, total) = (
+        stats.done_weight.to_string(),
+        stats.total_weight.to_string(),
+    );
+    let percent = format!("{:.0}", percentage(stats.done_weight, stats.total_weight));
+    let values = [
+        ("done", done.as_str()),
+        ("total", &total),
+        ("percent", &percent),
+    ];
+    println!("\t{}", t_fill("status.progress_weighted", &values));
+}
+
+// Prints the tasks as a JSON array to stdout, for consumption by other programs.
+fn print_tasks_json(tasks: &[Task]) {
+    let json = serde_json::to_string_pretty(tasks).expect("Serialization failed");
+    println!("{json}");
+}
+
+// Marks a task as complete at the specified index.
+// Recurring tasks with a valid due date stay pending and are rescheduled instead, one
+// period after either their due date or today, depending on `from`.
+fn complete_task(tasks: &mut [Task], index: usize, from: RecurrenceFrom) {
+    if let Some(task) = tasks.get_mut(index) {
+        // A task that can't be rescheduled any further is completed for good.
+        let next_due = match (task.recurrence, parse_due_date(task)) {
+            (Some(recurrence), Some(due)) => {
+                let start = match from {
+                    RecurrenceFrom::FromDue => due,
+                    RecurrenceFrom::FromToday => Local::now().date_naive(),
+                };
+                let next_due = next_due_date(start, recurrence);
+                if next_due.is_none() {
+                    let values = [("name", task.name.as_str()), ("date", &task.due_date)];
+                    println!("\n{}", t_fill("error.next_due_out_of_range", &values));
+                }
+                next_due
+            }
+            _ => None,
+        };
+        match next_due {
+            Some(next_due) => {
+                task.due_date = next_due.format(DATE_FORMAT).to_string();
+                task.in_progress = false;
+                let values = [("name", task.name.as_str()), ("date", &task.due_date)];
+                println!("\n{}", t_fill("status.next_due", &values));
+                log_action(&format!(
+                    "completed '{}', next due {}",
+                    task.name, task.due_date
+                ));
+            }
+            None => {
+                task.done = true;
+                task.in_progress = false;
+                task.completed_at = Some(Local::now());
+                print_time_to_complete(task);
+                log_action(&format!("completed '{}'", task.name));
+            }
+        }
+    } else {
+        println!("\n{}", t("error.invalid_task_index"));
+    }
+}
+
+// How many days snoozing a task in review mode pushes its due date back.
+const SNOOZE_DAYS: i64 = 7;
+
+// Walks through each pending task in turn, asking whether to keep, complete, delete,
+// snooze, or edit it, or skip it for now. Quitting ends the review early.
+fn review_tasks(tasks: &mut Vec<Task>, config: &Config) {
+    let mut reviewed = 0;
+    let mut i = 0;
+
+    while i < tasks.len() {
+        if tasks[i].done {
+            i += 1;
+            continue;
+        }
+
+        view_task_details(&tasks[i]);
+        let days = SNOOZE_DAYS.to_string();
+        println!("\n{}", t_fill("prompt.review", &[("days", &days)]));
+        match read_line().to_lowercase().as_str() {
+            "k" | "keep" => reviewed += 1,
+            "c" | "complete" => {
+                complete_task(tasks, i, config.recurrence_from);
+                reviewed += 1;
+            }
+            "d" | "delete" => {
+                match trash_task(tasks, i, &config.trash_path) {
+                    Ok(()) => {
+                        reviewed += 1;
+                        // The next task has moved into this one's place.
+                        continue;
+                    }
+                    Err(error) => {
+                        let error = error.to_string();
+                        println!("\n{}", t_fill("error.trash_task", &[("error", &error)]));
+                    }
+                }
+            }
+            "s" | "snooze" => {
+                snooze_task(&mut tasks[i], SNOOZE_DAYS);
+                reviewed += 1;
+            }
+            "e" | "edit" => {
+                edit_task(&mut tasks[i], config);
+                reviewed += 1;
+            }
+            "q" | "quit" => break,
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let reviewed = reviewed.to_string();
+    println!("\n{}", t_fill("status.reviewed", &[("count", &reviewed)]));
+}
+
+// Finds the pending tasks that have no due date, returning their indices in list order.
+fn undated_tasks(tasks: &[Task]) -> Vec<usize> {
+    tasks
+        .iter()
+        .enumerate()
+        .filter(|(_, task)| !task.done && task.due_date.is_empty())
+        .map(|(i, _)| i)
+        .collect()
+}
+
+// Walks through the pending tasks with no due date, offering to give each one a date or
+// snooze it, so "someday" tasks get scheduled instead of forgotten.
+fn schedule_undated(tasks: &mut [Task], config: &Config) {
+    let undated = undated_tasks(tasks);
+    if undated.is_empty() {
+        println!("\n{}", t("status.none_undated"));
+        return;
+    }
+
+    view_tasks_where(tasks, config, |task| !task.done && task.due_date.is_empty());
+
+    let mut scheduled = 0;
+    for index in undated {
+        let task = &mut tasks[index];
+        let days = SNOOZE_DAYS.to_string();
+        let values = [
+            ("name", task.name.as_str()),
+            ("format", &config.date_format),
+            ("days", &days),
+        ];
+        println!("\n{}", t_fill("prompt.schedule", &values));
+        loop {
+            let input = read_line();
+            match input.to_lowercase().as_str() {
+                "" => {}
+                "s" | "snooze" => {
+                    if snooze_task(task, SNOOZE_DAYS) {
+                        scheduled += 1;
+                    }
+                }
+                "q" | "quit" => {
+                    let scheduled = scheduled.to_string();
+                    println!("\n{}", t_fill("status.scheduled", &[("count", &scheduled)]));
+                    return;
+                }
+                _ => match NaiveDate::parse_from_str(&input, &config.date_format) {
+                    Ok(date) => {
+                        task.due_date = date.format(DATE_FORMAT).to_string();
+                        log_action(&format!("set '{}' due date - {}", task.name, task.due_date));
+                        scheduled += 1;
+                    }
+                    Err(_) => {
+                        let values = [("format", config.date_format.as_str())];
+                        println!("\n{}", t_fill("error.schedule_input", &values));
+                        continue;
+                    }
+                },
+            }
+            break;
+        }
+    }
+
+    let scheduled = scheduled.to_string();
+    println!("\n{}", t_fill("status.scheduled", &[("count", &scheduled)]));
+}
+
+// Pushes a task's due date back by `days`, counting from today if it is overdue or undated.
+// Returns false, leaving the date alone, if the new date would be out of range.
+fn snooze_task(task: &mut Task, days: i64) -> bool {
+    let today = Local::now().date_naive();
+    let from = parse_due_date(task).map_or(today, |due| due.max(today));
+    let Some(snoozed) = add_days(from, days) else {
+        println!(
+            "\n{}",
+            t_fill("error.snooze_out_of_range", &[("name", &task.name)])
+        );
+        return false;
+    };
+    task.due_date = snoozed.format(DATE_FORMAT).to_string();
+    let values = [("name", task.name.as_str()), ("date", &task.due_date)];
+    println!("\n{}", t_fill("status.snoozed", &values));
+    log_action(&format!("snoozed '{}' until {}", task.name, task.due_date));
+    true
+}
+
+// Moves the task at `index` to `status`. Marking it done completes it as usual, so
+// recurring tasks are rescheduled rather than finished.
+fn set_status(tasks: &mut [Task], index: usize, status: Status, from: RecurrenceFrom) {
+    if status == Status::Done {
+        complete_task(tasks, index, from);
+        return;
+    }
+
+    let Some(task) = tasks.get_mut(index) else {
+        println!("\n{}", t("error.invalid_task_index"));
+        return;
+    };
+
+    task.done = false;
+    task.completed_at = None;
+    task.in_progress = status == Status::InProgress;
+    let status_text = status.to_string();
+    let values = [("name", task.name.as_str()), ("status", &status_text)];
+    println!("\n{}", t_fill("status.now_status", &values));
+    log_action(&format!("set '{}' status - {status}", task.name));
+}
+
+// Marks every task as not yet started, returning how many were done or in progress.
+fn reset_all(tasks: &mut [Task]) -> usize {
+    let mut reset = 0;
+    for task in tasks.iter_mut() {
+        if task.done || task.in_progress {
+            reset += 1;
+        }
+        task.done = false;
+        task.in_progress = false;
+        task.completed_at = None;
+    }
+    log_action(&format!("reset {reset} task(s) to pending"));
+    reset
+}
+
+// Tells the user how long a just-completed task took, if its creation time is known.
+fn print_time_to_complete(task: &Task) {
+    if let (Some(created_at), Some(completed_at)) = (task.created_at, task.completed_at) {
+        let took = duration_human(created_at, completed_at);
+        let values = [("name", task.name.as_str()), ("duration", &took)];
+        println!("\n{}", t_fill("status.completed_after", &values));
+    }
+}
+
+// Describes the time between two moments in its largest whole unit, e.g. "4 days".
+fn duration_human(from: DateTime<Local>, to: DateTime<Local>) -> String {
+    let elapsed = to - from;
+    let (count, one, many) = if elapsed.num_days() > 0 {
+        (elapsed.num_days(), "duration.day", "duration.days")
+    } else if elapsed.num_hours() > 0 {
+        (elapsed.num_hours(), "duration.hour", "duration.hours")
+    } else if elapsed.num_minutes() > 0 {
+        (elapsed.num_minutes(), "duration.minute", "duration.minutes")
+    } else {
+        return t("duration.under_minute").to_string();
+    };
+
+    let key = if count == 1 { one } else { many };
+    t_fill(key, &[("count", &count.to_string())])
+}
+
+// Adds `minutes` to the time spent on a task, stopping at the most it can record rather
+// than overflowing.
+fn log_time(task: &mut Task, minutes: u32) {
+    task.time_spent_minutes = task.time_spent_minutes.saturating_add(minutes);
+    log_action(&format!("logged {minutes} minute(s) on '{}'", task.name));
+}
+
+// Lists completed tasks that have both an estimate and time logged, then how the total
+// time spent compares with the total estimated.
+fn print_estimate_report(tasks: &[Task]) {
+    let tracked: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.done && task.time_spent_minutes > 0)
+        .filter(|task| task.estimate_minutes.is_some_and(|estimate| estimate > 0))
+        .collect();
+
+    println!(); // newline
+    if tracked.is_empty() {
+        println!("\t{}", t("status.no_tracked"));
+        return;
+    }
+
+    // Summed as `u64` so the totals of many large entries can't overflow.
+    let mut estimated: u64 = 0;
+    let mut spent: u64 = 0;
+    let mut over = 0;
+    for task in &tracked {
+        let estimate = task.estimate_minutes.unwrap_or_default();
+        let spent_text = minutes_human(task.time_spent_minutes);
+        let estimate_text = minutes_human(estimate);
+        let values = [
+            ("name", task.name.as_str()),
+            ("spent", &spent_text),
+            ("estimate", &estimate_text),
+        ];
+        println!("\t{}", t_fill("status.estimate_row", &values));
+        estimated += u64::from(estimate);
+        spent += u64::from(task.time_spent_minutes);
+        if task.time_spent_minutes > estimate {
+            over += 1;
+        }
+    }
+
+    let percent = format!("{:.0}", spent as f64 / estimated as f64 * 100.0);
+    let (over, total) = (over.to_string(), tracked.len().to_string());
+    let values = [
+        ("percent", percent.as_str()),
+        ("over", &over),
+        ("total", &total),
+    ];
+    println!("\n\t{}", t_fill("status.estimate_summary", &values));
+}
+
+// Advances a due date by one period of its recurrence, or returns `None` if that would go
+// past the last date that can be represented.
+fn next_due_date(due: NaiveDate, recurrence: Recurrence) -> Option<NaiveDate> {
+    match recurrence {
+        Recurrence::Daily => add_days(due, 1),
+        Recurrence::Weekly => add_days(due, 7),
+        Recurrence::Monthly => due.checked_add_months(Months::new(1)),
+    }
+}
+
+// Checks whether every task this one is blocked by is done. Blockers that no longer
+// exist, such as archived tasks, don't hold it back.
+fn is_ready(task: &Task, all: &[Task]) -> bool {
+    task.blocked_by.iter().all(|id| {
+        all.iter()
+            .find(|other| other.id == *id)
+            .is_none_or(|blocker| blocker.done)
+    })
+}
+
+// Removes every `blocked_by` reference to a task ID that is no longer in the list, such as
+// one that was deleted or archived, reporting each and returning how many were removed.
+fn prune_orphan_deps(tasks: &mut [Task]) -> usize {
+    let ids: Vec<u32> = tasks.iter().map(|task| task.id).collect();
+    let mut pruned = 0;
+
+    for task in tasks.iter_mut() {
+        let (kept, orphaned): (Vec<u32>, Vec<u32>) =
+            task.blocked_by.iter().partition(|id| ids.contains(id));
+        for id in &orphaned {
+            let id_text = id.to_string();
+            let values = [("name", task.name.as_str()), ("id", &id_text)];
+            println!("\t{}", t_fill("status.pruned_dep", &values));
+            log_action(&format!(
+                "removed missing dependency #{id} from '{}'",
+                task.name
+            ));
+        }
+        pruned += orphaned.len();
+        task.blocked_by = kept;
+    }
+
+    pruned
+}
+
+// Checks whether making task `from` blocked by task `to` would create a dependency cycle,
+// by searching for a path of existing dependencies leading from `to` back to `from`.
+fn would_create_cycle(tasks: &[Task], from: u32, to: u32) -> bool {
+    let mut stack: Vec<u32> = vec![to];
+    let mut visited: Vec<u32> = vec![];
+
+    while let Some(id) = stack.pop() {
+        if id == from {
+            return true;
+        }
+        if visited.contains(&id) {
+            continue;
+        }
+        visited.push(id);
+
+        if let Some(task) = tasks.iter().find(|task| task.id == id) {
+            stack.extend(&task.blocked_by);
+        }
+    }
+
+    false
+}
+
+// Finds a task by name, preferring a case-insensitive exact match over a partial one.
+fn find_task_by_name(tasks: &[Task], name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    if name.is_empty() {
+        return None;
+    }
+
+    tasks
+        .iter()
+        .position(|task| task.name.to_lowercase() == name)
+        .or_else(|| {
+            tasks
+                .iter()
+                .position(|task| task.name.to_lowercase().contains(&name))
+        })
+}
+
+// Flips the done status of the task matching `name`, returning whether one was found.
+fn toggle_done_by_name(tasks: &mut [Task], name: &str) -> bool {
+    match find_task_by_name(tasks, name) {
+        Some(index) => {
+            let task = &mut tasks[index];
+            task.done = !task.done;
+            task.in_progress = false;
+            task.completed_at = if task.done { Some(Local::now()) } else { None };
+            let status = task.status().to_string();
+            let values = [("name", task.name.as_str()), ("status", &status)];
+            println!("\n{}", t_fill("status.now_status", &values));
+            print_time_to_complete(task);
+            log_action(&format!("set '{}' status - {status}", task.name));
+            true
+        }
+        None => false,
+    }
+}
+
+// Moves the task at the specified index into the trash file at `trash_path`.
+// The task is only removed from the list once the trash has been saved.
+fn trash_task(tasks: &mut Vec<Task>, index: usize, trash_path: &str) -> Result<()> {
+    let Some(task) = tasks.get(index) else {
+        return Err(TodoError::NotFound(index));
+    };
+
+    let mut trash = read_tasks_or_empty(trash_path)?;
+    trash.push(task.clone());
+    save_tasks(&trash, trash_path)?;
+
+    let task = tasks.remove(index);
+    println!("\n{}", t_fill("status.trashed", &[("name", &task.name)]));
+    log_action(&format!("deleted '{}'", task.name));
+    Ok(())
+}
+
+// Moves the task at the specified index of the trash file back into the task list.
+fn restore_task(tasks: &mut Vec<Task>, index: usize, trash_path: &str) -> Result<()> {
+    let mut trash = read_tasks_or_empty(trash_path)?;
+    if index >= trash.len() {
+        return Err(TodoError::NotFound(index));
+    }
+
+    let mut task = trash.remove(index);
+    save_tasks(&trash, trash_path)?;
+
+    // The task may have been trashed before IDs were kept unique across files.
+    if task.id == 0 || tasks.iter().any(|other| other.id == task.id) {
+        task.id = next_id(tasks);
+        log_action(&format!("gave '{}' new ID #{}", task.name, task.id));
+    }
+    println!("\n{}", t_fill("status.restored", &[("name", &task.name)]));
+    log_action(&format!("restored '{}'", task.name));
+    tasks.push(task);
+    Ok(())
+}
+
+// Permanently deletes every task in the trash file.
+fn purge_trash(trash_path: &str) -> Result<()> {
+    save_tasks(&[], trash_path)?;
+    log_action("emptied the trash");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_task_gives_tasks_added_in_a_loop_unique_ids_and_times() {
+        let mut tasks = vec![];
+        for i in 0..1000 {
+            let task = Task {
+                name: format!("Task {i}"),
+                ..Task::default()
+            };
+            add_task(&mut tasks, task);
+        }
+
+        let ids: HashSet<u32> = tasks.iter().map(|task| task.id).collect();
+        let times: HashSet<DateTime<Local>> =
+            tasks.iter().filter_map(|task| task.created_at).collect();
+        assert_eq!(ids.len(), tasks.len());
+        assert_eq!(times.len(), tasks.len());
+        assert!(!ids.contains(&0));
+    }
+
+    #[test]
+    fn cap_entries_keeps_only_the_newest_lines() {
+        let contents: String = (1..=7).map(|i| format!("entry {i}\n")).collect();
+        assert_eq!(
+            cap_entries(&contents, 5),
+            Some("entry 3\nentry 4\nentry 5\nentry 6\nentry 7\n".to_string())
+        );
+    }
+
+    #[test]
+    fn cap_entries_leaves_logs_within_the_cap_alone() {
+        assert_eq!(cap_entries("entry 1\nentry 2\n", 2), None);
+        assert_eq!(cap_entries("", 0), None);
+    }
+
+    #[test]
+    fn cap_entries_with_a_cap_of_zero_empties_the_log() {
+        assert_eq!(cap_entries("entry 1\nentry 2\n", 0), Some(String::new()));
+    }
+
+    #[test]
+    fn parse_index_explains_each_kind_of_rejected_input() {
+        assert_eq!(
+            parse_index("  ", 3),
+            Err("No task number entered!".to_string())
+        );
+        assert_eq!(
+            parse_index("two", 3),
+            Err("'two' isn't a number, enter a task number from the list!".to_string())
+        );
+        assert_eq!(
+            parse_index("-1", 3),
+            Err("Task numbers start at 1, so -1 isn't one!".to_string())
+        );
+        assert_eq!(
+            parse_index("0", 3),
+            Err("Task numbers start at 1, so 0 isn't one!".to_string())
+        );
+        assert_eq!(
+            parse_index("4", 3),
+            Err("There is no task 4, enter a number from 1 to 3!".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_index_turns_a_listed_number_into_an_index() {
+        assert_eq!(parse_index("1", 3), Ok(0));
+        assert_eq!(parse_index(" 3 ", 3), Ok(2));
+    }
+
+    #[test]
+    fn every_looked_up_key_has_english_text() {
+        let source = include_str!("main.rs");
+        for call in ["t(\"", "t_fill(\""] {
+            for (start, _) in source.match_indices(call) {
+                // Skips calls that only end in `t(`, such as `format(`.
+                let before = source[..start].chars().next_back();
+                if before.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+                    continue;
+                }
+                let rest = &source[start + call.len()..];
+                let key = &rest[..rest.find('"').unwrap()];
+                assert!(
+                    ENGLISH.iter().any(|(english_key, _)| *english_key == key),
+                    "`{key}` has no English text"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn t_fill_fills_placeholders_in_the_english_text() {
+        assert_eq!(
+            t_fill("status.tagged", &[("count", "2"), ("tag", "home")]),
+            "Tagged 2 task(s) with 'home'"
+        );
+        let missing = "no.such.key";
+        assert_eq!(t(missing), missing);
+    }
+
+    fn dated_task(due_date: &str) -> Task {
+        Task {
+            due_date: due_date.to_string(),
+            ..Task::default()
+        }
+    }
+
+    #[test]
+    fn shift_due_dates_moves_pending_dated_tasks() {
+        let mut tasks = vec![dated_task("2024-06-03"), dated_task("")];
+        assert_eq!(shift_due_dates(&mut tasks, 7), Some(1));
+        assert_eq!(tasks[0].due_date, "2024-06-10");
+        assert_eq!(tasks[1].due_date, "");
+    }
+
+    #[test]
+    fn shift_due_dates_rejects_offsets_out_of_range() {
+        let mut tasks = vec![dated_task("2024-06-03")];
+        assert_eq!(shift_due_dates(&mut tasks, 100_000_000), None);
+        assert_eq!(shift_due_dates(&mut tasks, i64::MIN), None);
+        assert_eq!(tasks[0].due_date, "2024-06-03");
+    }
+
+    #[test]
+    fn set_relative_due_date_rejects_offsets_out_of_range() {
+        let mut tasks = vec![dated_task("2024-06-03"), dated_task("")];
+        assert!(set_relative_due_date(&mut tasks, 1, 0, 9_999_999_999_999).is_err());
+        assert_eq!(tasks[1].due_date, "");
+        assert!(set_relative_due_date(&mut tasks, 1, 0, -3).is_ok());
+        assert_eq!(tasks[1].due_date, "2024-05-31");
+    }
+
+    #[test]
+    fn toggle_done_by_name_flips_between_todo_and_done() {
+        let mut tasks = vec![Task {
+            name: "Buy milk".to_string(),
+            in_progress: true,
+            ..Task::default()
+        }];
+        assert!(toggle_done_by_name(&mut tasks, "milk"));
+        assert!(tasks[0].status() == Status::Done);
+        assert!(toggle_done_by_name(&mut tasks, "milk"));
+        assert!(tasks[0].status() == Status::Todo);
+        assert!(!toggle_done_by_name(&mut tasks, "bread"));
+    }
+
+    #[test]
+    fn compute_stats_sums_the_largest_weights_without_overflowing() {
+        let heavy = Task {
+            weight: u32::MAX,
+            ..Task::default()
+        };
+        let tasks = vec![
+            Task {
+                done: true,
+                ..heavy.clone()
+            },
+            heavy,
+        ];
+        let stats = compute_stats(&tasks);
+        assert_eq!(stats.total_weight, 2 * u64::from(u32::MAX));
+        assert_eq!(stats.done_weight, u64::from(u32::MAX));
+        assert_eq!(percentage(stats.done_weight, stats.total_weight), 50.0);
+    }
+
+    #[test]
+    fn log_time_stops_at_the_most_minutes_a_task_can_record() {
+        let mut task = Task::default();
+        log_time(&mut task, 30);
+        assert_eq!(task.time_spent_minutes, 30);
+        log_time(&mut task, u32::MAX);
+        assert_eq!(task.time_spent_minutes, u32::MAX);
+    }
+
+    #[test]
+    fn is_due_soon_treats_a_huge_lead_time_as_always_due_soon() {
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        for days in [1_000_000_000, i64::MAX] {
+            let task = Task {
+                remind_before_days: Some(days),
+                ..dated_task("2030-01-01")
+            };
+            assert!(is_due_soon(&task, today));
+        }
+        assert!(!is_due_soon(&dated_task("2030-01-01"), today));
+    }
+
+    fn named_task(name: &str) -> Task {
+        Task {
+            name: name.to_string(),
+            ..Task::default()
+        }
+    }
+
+    fn names(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|task| task.name.as_str()).collect()
+    }
+
+    #[test]
+    fn pinning_a_later_task_lists_it_first_without_auto_sort() {
+        let config = Config::default();
+        assert!(!config.auto_sort);
+        let session = SessionState {
+            sort_key: Some(SortKey::Name),
+            ..SessionState::default()
+        };
+        let mut tasks = vec![named_task("b"), named_task("c"), named_task("a")];
+
+        tasks[1].pinned = true;
+        auto_sort(&mut tasks, &session, &config);
+        assert_eq!(names(&tasks), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn done_last_moves_tasks_completed_this_session_without_auto_sort() {
+        let config = Config {
+            done_last: true,
+            ..Config::default()
+        };
+        let session = SessionState::default();
+        let mut tasks = vec![named_task("a"), named_task("b"), named_task("c")];
+
+        complete_task(&mut tasks, 0, config.recurrence_from);
+        auto_sort(&mut tasks, &session, &config);
+        assert_eq!(names(&tasks), ["b", "c", "a"]);
+
+        tasks[2].pinned = true;
+        partition_tasks(&mut tasks, &config);
+        assert_eq!(names(&tasks), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn forecast_marks_dates_past_the_last_representable_day_as_beyond_range() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let estimated = |minutes| Task {
+            estimate_minutes: Some(minutes),
+            ..Task::default()
+        };
+        let tasks = [estimated(60), estimated(u32::MAX), estimated(u32::MAX)];
+        let projected = forecast(&tasks, 1, start);
+        assert_eq!(projected[0], (0, add_days(start, 59)));
+        assert_eq!(projected[1], (1, None));
+        assert_eq!(projected[2], (2, None));
+    }
+
+    #[test]
+    fn allow_more_tasks_passes_batches_that_fit_under_max_pending() {
+        let config = Config {
+            max_pending: Some(3),
+            ..Config::default()
+        };
+        let tasks = [named_task("a"), named_task("b")];
+        assert!(allow_more_tasks(&tasks, &config, 1));
+        assert!(allow_more_tasks(&[], &config, 3));
+        let full = [
+            named_task("a"),
+            named_task("b"),
+            named_task("c"),
+            named_task("d"),
+        ];
+        assert!(allow_more_tasks(&full, &config, 0));
+    }
+
+    #[test]
+    fn snoozing_or_repeating_past_the_last_date_leaves_the_task_alone() {
+        let last = NaiveDate::MAX.format(DATE_FORMAT).to_string();
+        for recurrence in [Recurrence::Daily, Recurrence::Weekly, Recurrence::Monthly] {
+            assert_eq!(next_due_date(NaiveDate::MAX, recurrence), None);
+        }
+
+        let mut task = dated_task(&last);
+        assert!(!snooze_task(&mut task, SNOOZE_DAYS));
+        assert_eq!(task.due_date, last);
+
+        let mut tasks = [Task {
+            recurrence: Some(Recurrence::Daily),
+            ..dated_task(&last)
+        }];
+        complete_task(&mut tasks, 0, RecurrenceFrom::FromDue);
+        assert!(tasks[0].done);
+        assert_eq!(tasks[0].due_date, last);
+    }
+
+    #[test]
+    fn import_csv_strips_windows_line_endings() {
+        let path = env::temp_dir().join(format!("todo-crlf-{}.csv", std::process::id()));
+        fs::write(
+            &path,
+            "name,desc,due_date,done\r\nBuy milk,2 litres,2024-06-03,false\r\n\"Call\r\nBob\",\"re: report\",,true\r\n",
+        )
+        .unwrap();
+        let tasks = import_csv(&path.to_string_lossy());
+        let _ = fs::remove_file(&path);
+
+        let tasks = tasks.unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].name, "Buy milk");
+        assert_eq!(tasks[0].due_date, "2024-06-03");
+        assert_eq!(tasks[1].name, "Call\nBob");
+        for task in &tasks {
+            assert!(!format!("{}{}{}", task.name, task.desc, task.due_date).contains('\r'));
+        }
+    }
+
+    fn home() -> String {
+        env::var("HOME")
+            .or_else(|_| env::var("USERPROFILE"))
+            .expect("a home directory is set")
+    }
+
+    #[test]
+    fn expand_path_replaces_a_leading_tilde_with_home() {
+        assert_eq!(expand_path("~"), PathBuf::from(home()));
+        assert_eq!(
+            expand_path("~/todo/tasks.json"),
+            PathBuf::from(format!("{}/todo/tasks.json", home()))
+        );
+    }
+
+    #[test]
+    fn expand_path_leaves_absolute_and_relative_paths_alone() {
+        assert_eq!(
+            expand_path("/var/tasks.json"),
+            PathBuf::from("/var/tasks.json")
+        );
+        assert_eq!(expand_path("tasks.json"), PathBuf::from("tasks.json"));
+        assert_eq!(
+            expand_path("lists/tasks.json"),
+            PathBuf::from("lists/tasks.json")
+        );
+    }
+
+    #[test]
+    fn expand_path_replaces_set_variables_in_both_forms() {
+        env::set_var("TODO_TEST_EXPAND_DIR", "/data");
+        assert_eq!(
+            expand_path("$TODO_TEST_EXPAND_DIR/tasks.json"),
+            PathBuf::from("/data/tasks.json")
+        );
+        assert_eq!(
+            expand_path("${TODO_TEST_EXPAND_DIR}tasks.json"),
+            PathBuf::from("/datatasks.json")
+        );
+    }
+
+    #[test]
+    fn expand_path_keeps_unset_variables_and_other_users_literal() {
+        env::remove_var("TODO_TEST_EXPAND_UNSET");
+        assert_eq!(
+            expand_path("$TODO_TEST_EXPAND_UNSET/tasks.json"),
+            PathBuf::from("$TODO_TEST_EXPAND_UNSET/tasks.json")
+        );
+        assert_eq!(
+            expand_path("~bob/tasks.json"),
+            PathBuf::from("~bob/tasks.json")
+        );
     }
 }